@@ -1,4 +1,8 @@
+use crate::error::ErrorFormat;
+use crate::github::policy::parse_duration;
+use crate::output::OutputFormat;
 use clap::{Parser, Subcommand};
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(name = "gh-reply")]
@@ -8,6 +12,49 @@ pub struct Cli {
     #[arg(short = 'R', long, global = true)]
     pub repo: Option<String>,
 
+    /// Output format (default: json; table for listings at a terminal)
+    #[arg(long, global = true, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Output JSON with only the given comma-separated fields
+    #[arg(long, global = true, value_name = "FIELDS", value_delimiter = ',')]
+    pub json: Option<Vec<String>>,
+
+    /// Filter JSON output using a jq expression
+    #[arg(long, global = true, value_name = "EXPRESSION")]
+    pub jq: Option<String>,
+
+    /// Format JSON output using a Go-like template, e.g. '{{range .items}}{{.path}}:{{.line}}\n{{end}}'
+    #[arg(short = 't', long, global = true, conflicts_with = "jq")]
+    pub template: Option<String>,
+
+    /// How errors are reported on stderr
+    #[arg(long, global = true, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
+
+    /// Report the GitHub API rate-limit budget and retries on stderr
+    #[arg(short = 'v', long, global = true)]
+    pub verbose: bool,
+
+    /// Stop a gh request that runs longer than this, e.g. 30s or 500ms; 0 waits forever
+    /// [env: GHREPLY_TIMEOUT] [git config: gh-reply.timeout] [default: 30s]
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// How often a failed query is retried. Mutations are only retried after a rate limit.
+    /// [env: GHREPLY_RETRIES] [git config: gh-reply.retries] [default: 3]
+    #[arg(long, global = true, value_name = "N")]
+    pub retries: Option<u32>,
+
+    /// Base delay of the exponential backoff between retries
+    /// [env: GHREPLY_RETRY_DELAY] [git config: gh-reply.retryDelay] [default: 500ms]
+    #[arg(long, global = true, value_name = "DURATION", value_parser = parse_duration)]
+    pub retry_delay: Option<Duration>,
+
+    /// Fetch fresh data instead of reusing recent responses cached under .git/
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -25,6 +72,22 @@ pub enum Commands {
         #[arg(long, default_value = "open")]
         state: String,
     },
+    /// Triage review threads in a full-screen terminal UI
+    Tui {
+        /// Pull request number
+        pr_number: u32,
+    },
+    /// Step through unresolved threads one by one, drafting an answer for each
+    Walk {
+        /// Pull request number
+        pr_number: u32,
+        /// Start at this thread (ID or 1-based index)
+        #[arg(long)]
+        from: Option<String>,
+        /// Canned reply offered by the snippet prompt (repeatable; replaces the defaults)
+        #[arg(long = "snippet", value_name = "TEXT")]
+        snippets: Vec<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -51,6 +114,12 @@ pub enum CommentAction {
         /// Items per page
         #[arg(long, default_value = "10")]
         per_page: usize,
+        /// Only show outdated threads
+        #[arg(long, conflicts_with = "no_outdated")]
+        outdated: bool,
+        /// Hide outdated threads
+        #[arg(long)]
+        no_outdated: bool,
     },
     /// Show details of a specific comment thread
     Show {
@@ -65,6 +134,12 @@ pub enum CommentAction {
         /// Include detail fields (url, bodyHTML, diffHunk, commitOid)
         #[arg(long)]
         detail: Option<String>,
+        /// Print the thread as text with N lines of code around it (default: 3)
+        #[arg(long, num_args = 0..=1, default_missing_value = "3", value_name = "N")]
+        with_context: Option<u32>,
+        /// Take the code from the file at local HEAD instead of the diff hunk
+        #[arg(long, requires = "with_context")]
+        local: bool,
     },
     /// Reply to a comment thread
     Reply {
@@ -85,6 +160,141 @@ pub enum CommentAction {
         #[arg(long)]
         dry_run: bool,
     },
+    /// Reply with a suggestion block built from local changes to the commented lines
+    Suggest {
+        /// Pull request number
+        pr_number: u32,
+        /// Thread ID (e.g., PRRT_kwDOQZVIxM5nXEc_)
+        #[arg(group = "thread", required = true)]
+        thread_id: Option<String>,
+        /// Thread index (1-based, e.g., 1, 2, 3)
+        #[arg(short = 'i', long, group = "thread")]
+        index: Option<usize>,
+        /// Message to put before the suggestion block
+        #[arg(short = 'm', long)]
+        message: Option<String>,
+        /// Resolve the thread after replying
+        #[arg(short = 'r', long)]
+        resolve: bool,
+        /// Save as a draft instead of posting
+        #[arg(long)]
+        draft: bool,
+        /// Dry run - don't actually send
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Apply reviewer suggestion blocks to the working tree
+    Apply {
+        /// Pull request number
+        pr_number: u32,
+        /// Thread IDs or 1-based indexes
+        targets: Vec<String>,
+        /// Select threads by filter (author:NAME, last-author:NAME, path:PREFIX, contains:TEXT, resolved:BOOL, outdated:BOOL; @me for yourself)
+        #[arg(long)]
+        filter: Option<String>,
+        /// Draft a reply for each applied suggestion (default: "Applied in {{short_commit}}")
+        #[arg(long, num_args = 0..=1, default_missing_value = "Applied in {{short_commit}}", value_name = "TEMPLATE")]
+        draft_reply: Option<String>,
+        /// Resolve the threads when the drafted replies are sent
+        #[arg(short = 'r', long, requires = "draft_reply")]
+        resolve: bool,
+        /// Dry run - only report the suggestions found
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Start a new review thread on a diff line
+    New {
+        /// Pull request number
+        pr_number: u32,
+        /// File path relative to the repository root
+        #[arg(long)]
+        path: String,
+        /// Line number (last line of the range for multi-line comments)
+        #[arg(long)]
+        line: u32,
+        /// First line of a multi-line range
+        #[arg(long)]
+        start_line: Option<u32>,
+        /// Diff side: LEFT (base) or RIGHT (head)
+        #[arg(long, default_value = "RIGHT", ignore_case = true, value_parser = ["LEFT", "RIGHT"])]
+        side: String,
+        /// Comment message
+        message: String,
+        /// Save as a draft instead of posting
+        #[arg(long)]
+        draft: bool,
+        /// Dry run - don't actually send
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Resolve one or more comment threads
+    Resolve {
+        /// Pull request number
+        pr_number: u32,
+        /// Thread IDs or 1-based indexes
+        targets: Vec<String>,
+        /// Select threads by filter (author:NAME, last-author:NAME, path:PREFIX, contains:TEXT, resolved:BOOL, outdated:BOOL; @me for yourself)
+        #[arg(long)]
+        filter: Option<String>,
+        /// Select unresolved outdated threads (combined with --filter)
+        #[arg(long)]
+        outdated: bool,
+        /// Post this reply to each thread before resolving it (supports template variables)
+        #[arg(long, value_name = "MESSAGE")]
+        reply: Option<String>,
+        /// Dry run - don't actually resolve
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Unresolve one or more comment threads
+    Unresolve {
+        /// Pull request number
+        pr_number: u32,
+        /// Thread IDs or 1-based indexes
+        targets: Vec<String>,
+        /// Select threads by filter (author:NAME, last-author:NAME, path:PREFIX, contains:TEXT, resolved:BOOL, outdated:BOOL; @me for yourself)
+        #[arg(long)]
+        filter: Option<String>,
+        /// Dry run - don't actually unresolve
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Edit a previously posted reply
+    Edit {
+        /// Comment ID (e.g., PRRC_kwDOQZVIxM6Xyz12)
+        #[arg(group = "comment", required = true)]
+        comment_id: Option<String>,
+        /// Pull request number (used with --thread)
+        #[arg(long, requires = "thread")]
+        pr: Option<u32>,
+        /// Edit your last reply in this thread (thread ID or 1-based index)
+        #[arg(long, group = "comment", requires = "pr")]
+        thread: Option<String>,
+        /// New comment body
+        #[arg(long, group = "source", required = true)]
+        body: Option<String>,
+        /// Edit the current body in $VISUAL/$EDITOR
+        #[arg(long, group = "source")]
+        editor: bool,
+        /// Dry run - don't actually update
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Delete a previously posted reply
+    Delete {
+        /// Comment ID (e.g., PRRC_kwDOQZVIxM6Xyz12)
+        #[arg(group = "comment", required = true)]
+        comment_id: Option<String>,
+        /// Pull request number (used with --thread)
+        #[arg(long, requires = "thread")]
+        pr: Option<u32>,
+        /// Delete your last reply in this thread (thread ID or 1-based index)
+        #[arg(long, group = "comment", requires = "pr")]
+        thread: Option<String>,
+        /// Dry run - don't actually delete
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Draft-related commands
     Draft {
         #[command(subcommand)]
@@ -125,6 +335,48 @@ pub enum DraftAction {
         /// Dry run - don't actually send
         #[arg(long)]
         dry_run: bool,
+        /// Submit all drafts together as a single review
+        #[arg(long)]
+        as_review: bool,
+        /// Review summary body (with --as-review)
+        #[arg(long, requires = "as_review")]
+        summary: Option<String>,
+        /// Review event: COMMENT, APPROVE, REQUEST_CHANGES (with --as-review)
+        #[arg(
+            long,
+            default_value = "COMMENT",
+            ignore_case = true,
+            value_parser = ["COMMENT", "APPROVE", "REQUEST_CHANGES"],
+            requires = "as_review"
+        )]
+        event: String,
+    },
+    /// Draft a resolving reply for each thread whose lines were changed by local commits
+    SuggestFixed {
+        /// Pull request number
+        pr_number: u32,
+        /// Reply template; {{short_commit}} and {{local_commit}} refer to the fixing commit
+        #[arg(short = 'm', long, default_value = "Fixed in {{short_commit}}")]
+        message: String,
+        /// Dry run - only report the detected threads
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Create drafts from `Addresses-thread:` / `Reply:` commit message trailers
+    FromCommits {
+        /// Pull request number
+        pr_number: u32,
+        /// Commit range to scan (default: origin/<base branch>..HEAD)
+        range: Option<String>,
+        /// Default reply for threads without a `Reply:` trailer
+        #[arg(short = 'm', long, default_value = "Addressed in {{short_commit}}")]
+        message: String,
+        /// Resolve the threads when sending
+        #[arg(short = 'r', long)]
+        resolve: bool,
+        /// Dry run - only report the drafts that would be created
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Clear all draft replies
     Clear {
@@ -1,7 +1,17 @@
 use crate::error::Result;
 use crate::github::GhClient;
+use crate::output;
 use crate::context::ContextBuilder;
 use crate::vars::TemplateExpander;
+use crate::policy::{ResolveDecision, ResolvePolicy};
+use crate::filter::ThreadFilter;
+use crate::editor;
+use crate::store::DraftStore;
+use crate::types::{DraftEntry, LineStatus, NewThreadTarget, PostedComment, ReviewThread, ThreadComment};
+use std::collections::HashMap;
+use crate::git;
+use crate::suggestion;
+use crate::snippet;
 
 /// Resolve thread identifier to thread ID
 /// Either thread_id or index must be provided
@@ -22,27 +32,231 @@ pub async fn resolve_thread_id(
 
             // Check if index is valid (1-based)
             if idx == 0 || idx > threads.len() {
-                return Err(crate::error::GhReplyError::GhError(
-                    format!("Thread index {} is out of range (1-{})", idx, threads.len())
-                ));
+                return Err(crate::error::GhReplyError::IndexOutOfRange { index: idx, total: threads.len() });
             }
 
             // Return thread ID (index is 1-based)
             Ok(threads[idx - 1].id.clone())
         }
         (Some(_), Some(_)) => {
-            Err(crate::error::GhReplyError::GhError(
+            Err(crate::error::GhReplyError::InvalidInput(
                 "Cannot specify both thread_id and index".to_string()
             ))
         }
         (None, None) => {
-            Err(crate::error::GhReplyError::GhError(
+            Err(crate::error::GhReplyError::InvalidInput(
                 "Must specify either thread_id or --index".to_string()
             ))
         }
     }
 }
 
+/// Fetch a single thread by ID or 1-based index
+pub async fn find_thread(
+    client: &GhClient,
+    pr_number: u32,
+    thread_id: Option<&str>,
+    index: Option<usize>,
+) -> Result<ReviewThread> {
+    let threads = client.get_review_threads(pr_number).await?;
+    let thread_id = match (thread_id, index) {
+        (None, Some(idx)) => {
+            if idx == 0 || idx > threads.len() {
+                return Err(crate::error::GhReplyError::IndexOutOfRange { index: idx, total: threads.len() });
+            }
+            threads[idx - 1].id.clone()
+        }
+        // A thread ID, or an invalid combination reported without fetching
+        _ => resolve_thread_id(client, pr_number, thread_id, index).await?,
+    };
+
+    match threads.into_iter().find(|t| t.id == thread_id) {
+        Some(thread) => Ok(thread),
+        None => Err(crate::error::GhReplyError::not_found("Thread", thread_id, Some(client.pr_location(pr_number).await))),
+    }
+}
+
+/// Select threads from explicit targets (thread IDs or 1-based indexes) and an
+/// optional filter expression. With no targets, every thread matching the
+/// filter is selected, except those already in the `skip_resolved` state.
+/// With a filter, thread IDs missing from the thread list are reported as
+/// not found rather than silently dropped.
+pub async fn select_threads(
+    client: &GhClient,
+    pr_number: u32,
+    targets: &[String],
+    filter: Option<&str>,
+    skip_resolved: bool,
+) -> Result<Vec<String>> {
+    let filter = parse_selection(targets, filter)?;
+    let needs_threads = filter.is_some() || targets.iter().any(|t| t.parse::<usize>().is_ok());
+    if !needs_threads {
+        return Ok(targets.to_vec());
+    }
+
+    let threads = client.get_review_threads(pr_number).await?;
+    select_from_threads(client, pr_number, &threads, targets, filter.as_ref(), skip_resolved).await
+}
+
+/// Check that a selection names at least one thread or a filter, and parse
+/// the filter
+pub fn parse_selection(targets: &[String], filter: Option<&str>) -> Result<Option<ThreadFilter>> {
+    if targets.is_empty() && filter.is_none() {
+        return Err(crate::error::GhReplyError::InvalidInput(
+            "Must specify at least one thread or --filter".to_string()
+        ));
+    }
+    filter.map(ThreadFilter::parse).transpose()
+}
+
+/// `select_threads` over an already fetched thread list
+pub async fn select_from_threads(
+    client: &GhClient,
+    pr_number: u32,
+    threads: &[ReviewThread],
+    targets: &[String],
+    filter: Option<&ThreadFilter>,
+    skip_resolved: bool,
+) -> Result<Vec<String>> {
+    let viewer = match filter {
+        Some(f) if f.needs_viewer() => client.get_authenticated_user().await?,
+        _ => String::new(),
+    };
+
+    let candidates: Vec<String> = if targets.is_empty() {
+        threads
+            .iter()
+            .filter(|t| t.is_resolved != skip_resolved)
+            .map(|t| t.id.clone())
+            .collect()
+    } else {
+        let mut ids = Vec::new();
+        for target in targets {
+            let id = match target.parse::<usize>() {
+                Ok(idx) => {
+                    if idx == 0 || idx > threads.len() {
+                        return Err(crate::error::GhReplyError::IndexOutOfRange { index: idx, total: threads.len() });
+                    }
+                    threads[idx - 1].id.clone()
+                }
+                Err(_) if filter.is_none() || threads.iter().any(|t| &t.id == target) => target.clone(),
+                Err(_) => {
+                    return Err(crate::error::GhReplyError::not_found("Thread", target.as_str(), Some(client.pr_location(pr_number).await)));
+                }
+            };
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    };
+
+    let Some(filter) = filter else {
+        return Ok(candidates);
+    };
+
+    Ok(candidates
+        .into_iter()
+        .filter(|id| {
+            threads
+                .iter()
+                .find(|t| &t.id == id)
+                .is_some_and(|t| filter.matches(t, &viewer))
+        })
+        .collect())
+}
+
+/// Apply the resolve policy to a thread without resolving it, reporting
+/// skipped threads on stderr
+pub async fn resolve_decision(
+    client: &GhClient,
+    policy: ResolvePolicy,
+    thread_id: &str,
+    viewer: &str,
+) -> Result<ResolveDecision> {
+    let author = if policy.needs_author() {
+        client.get_thread_author(thread_id).await?
+    } else {
+        None
+    };
+
+    let decision = policy.check(thread_id, author.as_ref(), viewer);
+    if let ResolveDecision::Skip(reason) = &decision {
+        eprintln!("Thread {} not resolved: {}", thread_id, reason);
+    }
+    Ok(decision)
+}
+
+/// Resolve a thread if the resolve policy allows it.
+/// Returns the decision so callers can report skipped threads.
+pub async fn resolve_with_policy(
+    client: &GhClient,
+    policy: ResolvePolicy,
+    thread_id: &str,
+    viewer: &str,
+) -> Result<ResolveDecision> {
+    let decision = resolve_decision(client, policy, thread_id, viewer).await?;
+    if decision == ResolveDecision::Resolve {
+        client.resolve_thread(thread_id).await?;
+    }
+    Ok(decision)
+}
+
+/// Map each thread's line onto local HEAD via `git diff <commit> HEAD`.
+/// Threads whose commit is not available locally are left untouched.
+pub async fn annotate_current_lines(threads: &mut [ReviewThread]) {
+    let Ok(head) = git::git(&["rev-parse", "HEAD"]).await else {
+        return;
+    };
+    let head = head.trim();
+
+    // Threads on the same file and commit share one diff
+    let mut diffs: HashMap<(String, String), Option<Vec<git::Hunk>>> = HashMap::new();
+
+    for thread in threads.iter_mut() {
+        let (Some((start, end)), Some(commit)) = (thread.line_range(), thread.commit_oid.clone()) else {
+            continue;
+        };
+        if thread.diff_side.as_deref() == Some("LEFT") {
+            continue;
+        }
+
+        let key = (commit, thread.path.clone());
+        if !diffs.contains_key(&key) {
+            let hunks = diff_since(&key.0, head, &thread.path).await;
+            diffs.insert(key.clone(), hunks);
+        }
+        let hunks = &diffs[&key];
+        let Some(hunks) = hunks else {
+            continue;
+        };
+
+        let mapped = git::map_range(hunks, start, end);
+        let status = if mapped.deleted {
+            LineStatus::Deleted
+        } else if mapped.changed {
+            LineStatus::Rewritten
+        } else if mapped.end != end {
+            LineStatus::Moved
+        } else {
+            LineStatus::Unchanged
+        };
+        thread.current_line = if mapped.deleted { None } else { Some(mapped.end) };
+        thread.line_status = Some(status);
+    }
+}
+
+/// Hunks between `commit` and `head` for `path`, if the commit is available
+/// locally and the file existed in it
+async fn diff_since(commit: &str, head: &str, path: &str) -> Option<Vec<git::Hunk>> {
+    git::ensure_commit(commit).await.ok()?;
+    if !git::exists_at(commit, path).await {
+        return None;
+    }
+    git::diff_hunks(commit, Some(head), path).await.ok()
+}
+
+#[allow(clippy::too_many_arguments)]
 pub async fn list(
     client: &GhClient,
     pr_number: u32,
@@ -52,26 +266,27 @@ pub async fn list(
     detail: Option<&str>,
     page: usize,
     per_page: usize,
+    outdated: Option<bool>,
 ) -> Result<()> {
     // Check for unimplemented options
     if label.is_some() {
-        return Err(crate::error::GhReplyError::GhError(
+        return Err(crate::error::GhReplyError::InvalidInput(
             "Option --label is not yet implemented".to_string()
         ));
     }
     if comment_filter.is_some() {
-        return Err(crate::error::GhReplyError::GhError(
+        return Err(crate::error::GhReplyError::InvalidInput(
             "Option --comment-filter is not yet implemented".to_string()
         ));
     }
     if detail.is_some() {
-        return Err(crate::error::GhReplyError::GhError(
+        return Err(crate::error::GhReplyError::InvalidInput(
             "Option --detail is not yet implemented".to_string()
         ));
     }
 
     // Ensure gh CLI is available
-    GhClient::ensure_gh_available()?;
+    GhClient::ensure_gh_available().await?;
 
     // Fetch review threads
     let threads = client.get_review_threads(pr_number).await?;
@@ -80,18 +295,22 @@ pub async fn list(
     let filtered_threads: Vec<_> = threads
         .iter()
         .filter(|t| include_resolved || !t.is_resolved)
+        .filter(|t| outdated.is_none_or(|o| t.is_outdated == o))
         .collect();
 
     // Apply pagination
     let start = (page - 1) * per_page;
-    let end = start + per_page;
-    let paginated_threads: Vec<_> = filtered_threads
+    let mut paginated_threads: Vec<_> = filtered_threads
         .into_iter()
         .skip(start)
         .take(per_page)
+        .cloned()
         .collect();
 
-    // Output as JSON
+    // Map thread lines onto local HEAD
+    annotate_current_lines(&mut paginated_threads).await;
+
+    // Output as a table at the terminal, JSON otherwise
     let output = serde_json::json!({
         "total": threads.len(),
         "page": page,
@@ -99,36 +318,99 @@ pub async fn list(
         "items": paginated_threads,
     });
 
-    println!("{}", serde_json::to_string_pretty(&output)?);
+    output::print_listing(&output)?;
     Ok(())
 }
 
-pub async fn show(client: &GhClient, pr_number: u32, thread_id: Option<&str>, index: Option<usize>, detail: Option<&str>) -> Result<()> {
+/// Render a thread as text with the code around its commented lines,
+/// taken from the diff hunk or, with `local`, from the file at local HEAD
+pub async fn render_with_context(client: &GhClient, thread: &ReviewThread, context: u32, local: bool) -> Result<String> {
+    use std::io::IsTerminal;
+
+    let mut out = String::new();
+    let location = match thread.line_range() {
+        Some((start, end)) if start != end => format!("{}:{}-{}", thread.path, start, end),
+        Some((_, end)) => format!("{}:{}", thread.path, end),
+        None => thread.path.clone(),
+    };
+    out.push_str(&format!(
+        "{}  {}{}\n",
+        thread.id,
+        location,
+        if thread.is_resolved { " (resolved)" } else { "" }
+    ));
+    out.push_str(&format!("{}: {}\n\n", thread.author.as_deref().unwrap_or("unknown"), thread.body.trim()));
+
+    let no_lines = "(no line information: file-level or outdated thread)\n";
+    let lines = if local && thread.diff_side.as_deref() != Some("LEFT") {
+        let Some((start, end)) = thread.line_range() else {
+            out.push_str(no_lines);
+            return Ok(out);
+        };
+        // Follow the thread onto local HEAD when its lines moved
+        let (start, end) = match thread.current_line {
+            Some(current) => (current - (end - start).min(current - 1), current),
+            None => (start, end),
+        };
+        if thread.line_status == Some(LineStatus::Deleted) {
+            out.push_str("(the commented lines were deleted at local HEAD)\n");
+            return Ok(out);
+        }
+        let text = git::file_at("HEAD", &thread.path).await?;
+        snippet::from_file(&text, start, end, context)
+    } else {
+        // The diff hunk is numbered at the commit the comment was made on
+        let Some((start, end)) = thread.original_line_range() else {
+            out.push_str(no_lines);
+            return Ok(out);
+        };
+        let hunk = client.get_thread_diff_hunk(&thread.id).await?.unwrap_or_default();
+        snippet::from_diff_hunk(&hunk, thread.diff_side.as_deref(), start, end, context)
+    };
+
+    if lines.is_empty() {
+        out.push_str("(no code context available)\n");
+    } else {
+        let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+        out.push_str(&snippet::render(&lines, color));
+    }
+    Ok(out)
+}
+
+pub async fn show(
+    client: &GhClient,
+    pr_number: u32,
+    thread_id: Option<&str>,
+    index: Option<usize>,
+    detail: Option<&str>,
+    with_context: Option<u32>,
+    local: bool,
+) -> Result<()> {
     // Check for unimplemented options
     if detail.is_some() {
-        return Err(crate::error::GhReplyError::GhError(
+        return Err(crate::error::GhReplyError::InvalidInput(
             "Option --detail is not yet implemented".to_string()
         ));
     }
 
     // Ensure gh CLI is available
-    GhClient::ensure_gh_available()?;
+    GhClient::ensure_gh_available().await?;
 
-    // Resolve thread identifier to thread ID
-    let thread_id = resolve_thread_id(client, pr_number, thread_id, index).await?;
+    // Fetch the thread by ID or index
+    let thread = find_thread(client, pr_number, thread_id, index).await?;
 
-    // Fetch review threads
-    let threads = client.get_review_threads(pr_number).await?;
+    // Map the thread line onto local HEAD
+    let mut thread = [thread];
+    annotate_current_lines(&mut thread).await;
 
-    // Find the specific thread
-    let thread = threads.iter()
-        .find(|t| t.id == thread_id)
-        .ok_or_else(|| crate::error::GhReplyError::GhError(
-            format!("Thread {} not found", thread_id)
-        ))?;
+    // Text view with surrounding code
+    if let Some(context) = with_context {
+        print!("{}", render_with_context(client, &thread[0], context, local).await?);
+        return Ok(());
+    }
 
-    // Output as JSON
-    println!("{}", serde_json::to_string_pretty(thread)?);
+    // Output in the selected format (JSON by default)
+    output::print(&thread[0])?;
     Ok(())
 }
 
@@ -142,7 +424,10 @@ pub async fn reply(
     dry_run: bool,
 ) -> Result<()> {
     // Ensure gh CLI is available
-    GhClient::ensure_gh_available()?;
+    GhClient::ensure_gh_available().await?;
+
+    // Fail early on an invalid GHREPLY_RESOLVE value
+    let policy = ResolvePolicy::from_env()?;
 
     // Resolve thread identifier to thread ID
     let thread_id = resolve_thread_id(client, pr_number, thread_id, index).await?;
@@ -164,19 +449,664 @@ pub async fn reply(
         eprintln!("Thread: {}", thread_id);
         eprintln!("Message: {}", expanded_message);
         if resolve {
-            eprintln!("Would resolve thread");
+            eprintln!("Would resolve thread (resolve policy: {})", policy);
         }
         return Ok(());
     }
 
     // Post the reply
-    client.post_reply(pr_number, &thread_id, &expanded_message).await?;
+    let comment = client.post_reply(pr_number, &thread_id, &expanded_message).await?;
 
-    // Resolve thread if requested
+    // Resolve thread if requested and allowed by the resolve policy
     if resolve {
-        client.resolve_thread(&thread_id).await?;
+        resolve_with_policy(client, policy, &thread_id, &context.base.username).await?;
     }
 
     eprintln!("Reply posted successfully");
+    output::print(&comment)?;
+    Ok(())
+}
+
+pub async fn resolve(
+    client: &GhClient,
+    pr_number: u32,
+    targets: &[String],
+    filter: Option<&str>,
+    outdated: bool,
+    reply: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    // Fail early on an invalid GHREPLY_RESOLVE value
+    let policy = ResolvePolicy::from_env()?;
+
+    // --outdated is shorthand for an extra `outdated:true` filter term
+    let filter = match (filter, outdated) {
+        (Some(filter), true) => Some(format!("{} outdated:true", filter)),
+        (None, true) => Some("outdated:true".to_string()),
+        (filter, false) => filter.map(|f| f.to_string()),
+    };
+    let thread_ids = select_threads(client, pr_number, targets, filter.as_deref(), true).await?;
+
+    if dry_run {
+        eprintln!("Dry run mode - would resolve {} thread(s) (resolve policy: {}):", thread_ids.len(), policy);
+        for thread_id in &thread_ids {
+            eprintln!("  Thread {}", thread_id);
+        }
+        if let Some(reply) = reply {
+            eprintln!("Would reply to each thread the policy resolves first: {}", reply);
+        }
+        return Ok(());
+    }
+
+    resolve_threads(client, pr_number, policy, &thread_ids, reply).await
+}
+
+/// Resolve the selected threads as the resolve policy allows. With `reply`,
+/// each thread that will be resolved gets the reply first; threads the
+/// policy skips are left without one.
+pub async fn resolve_threads(
+    client: &GhClient,
+    pr_number: u32,
+    policy: ResolvePolicy,
+    thread_ids: &[String],
+    reply: Option<&str>,
+) -> Result<()> {
+    let viewer = if policy.needs_author() {
+        client.get_authenticated_user().await?
+    } else {
+        String::new()
+    };
+
+    let context_builder = ContextBuilder::new(client.clone());
+    let mut replied = Vec::new();
+    let mut resolved = Vec::new();
+    let mut skipped = Vec::new();
+    for thread_id in thread_ids {
+        if let ResolveDecision::Skip(reason) = resolve_decision(client, policy, thread_id, &viewer).await? {
+            skipped.push(serde_json::json!({
+                "id": thread_id,
+                "reason": reason,
+            }));
+            continue;
+        }
+
+        if let Some(reply) = reply {
+            let context = context_builder
+                .build_reply_context(&pr_number.to_string(), thread_id)
+                .await?;
+            let message = TemplateExpander::from_context(&context).expand(reply)?;
+            replied.push(client.post_reply(pr_number, thread_id, &message).await?);
+        }
+
+        client.resolve_thread(thread_id).await?;
+        resolved.push(thread_id.clone());
+    }
+
+    let mut output = serde_json::json!({
+        "resolved": resolved,
+        "skipped": skipped,
+    });
+    if reply.is_some() {
+        output["replied"] = serde_json::to_value(&replied)?;
+    }
+
+    output::print(&output)?;
+    Ok(())
+}
+
+pub async fn unresolve(
+    client: &GhClient,
+    pr_number: u32,
+    targets: &[String],
+    filter: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    let thread_ids = select_threads(client, pr_number, targets, filter, false).await?;
+
+    if dry_run {
+        eprintln!("Dry run mode - would unresolve {} thread(s):", thread_ids.len());
+        for thread_id in &thread_ids {
+            eprintln!("  Thread {}", thread_id);
+        }
+        return Ok(());
+    }
+
+    for thread_id in &thread_ids {
+        client.unresolve_thread(thread_id).await?;
+    }
+
+    let output = serde_json::json!({
+        "unresolved": thread_ids,
+    });
+
+    output::print(&output)?;
+    Ok(())
+}
+
+/// Resolve an edit/delete target to a comment ID: either the comment ID itself
+/// or the authenticated user's last reply in a thread (ID or 1-based index)
+async fn resolve_comment_id(
+    client: &GhClient,
+    comment_id: Option<&str>,
+    pr_number: Option<u32>,
+    thread: Option<&str>,
+) -> Result<String> {
+    match (comment_id, pr_number, thread) {
+        (Some(id), _, None) => Ok(id.to_string()),
+        (None, Some(pr_number), Some(thread)) => {
+            let thread_ids = select_threads(client, pr_number, &[thread.to_string()], None, true).await?;
+            let thread_id = &thread_ids[0];
+            let viewer = client.get_authenticated_user().await?;
+            if viewer.is_empty() {
+                return Err(crate::error::GhReplyError::GhError(
+                    "Failed to determine the authenticated user".to_string()
+                ));
+            }
+            client
+                .get_last_comment_by(thread_id, &viewer)
+                .await?
+                .ok_or_else(|| crate::error::GhReplyError::not_found(&format!("Reply by {} in thread", viewer), thread_id.as_str(), None))
+        }
+        (None, None, Some(_)) => Err(crate::error::GhReplyError::InvalidInput(
+            "--thread requires --pr".to_string()
+        )),
+        _ => Err(crate::error::GhReplyError::InvalidInput(
+            "Must specify either comment_id or --pr with --thread".to_string()
+        )),
+    }
+}
+
+pub async fn edit(
+    client: &GhClient,
+    comment_id: Option<&str>,
+    pr_number: Option<u32>,
+    thread: Option<&str>,
+    body: Option<&str>,
+    use_editor: bool,
+    dry_run: bool,
+) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    let comment_id = resolve_comment_id(client, comment_id, pr_number, thread).await?;
+
+    let new_body = match body {
+        Some(body) => body.to_string(),
+        None if use_editor => {
+            let current = client.get_comment_body(&comment_id).await?;
+            editor::edit_text(&current).await?
+        }
+        None => {
+            return Err(crate::error::GhReplyError::InvalidInput(
+                "Must specify either --body or --editor".to_string()
+            ))
+        }
+    };
+
+    if new_body.trim().is_empty() {
+        return Err(crate::error::GhReplyError::InvalidInput(
+            "Refusing to update comment with an empty body (use `comment delete` instead)".to_string()
+        ));
+    }
+
+    if dry_run {
+        eprintln!("Dry run mode - would update comment:");
+        eprintln!("Comment: {}", comment_id);
+        eprintln!("Body: {}", new_body);
+        return Ok(());
+    }
+
+    let comment = client.update_comment(&comment_id, &new_body).await?;
+
+    output::print(&comment)?;
+    Ok(())
+}
+
+pub async fn delete(
+    client: &GhClient,
+    comment_id: Option<&str>,
+    pr_number: Option<u32>,
+    thread: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    let comment_id = resolve_comment_id(client, comment_id, pr_number, thread).await?;
+
+    if dry_run {
+        eprintln!("Dry run mode - would delete comment {}", comment_id);
+        return Ok(());
+    }
+
+    client.delete_comment(&comment_id).await?;
+
+    let output = serde_json::json!({
+        "deleted": comment_id,
+    });
+
+    output::print(&output)?;
+    Ok(())
+}
+
+/// Post a new review thread immediately, wrapped in a single-comment review
+pub async fn post_new_thread(
+    client: &GhClient,
+    pr_number: u32,
+    target: &NewThreadTarget,
+    body: &str,
+) -> Result<PostedComment> {
+    let pr_node_id = client.get_pr_node_id(pr_number).await?;
+    let review_id = client.start_review(&pr_node_id).await?;
+
+    let comment = match client.add_review_thread(&review_id, target, body).await {
+        Ok(comment) => comment,
+        Err(e) => {
+            // Don't leave an empty pending review behind
+            client.delete_review(&review_id).await.ok();
+            return Err(e);
+        }
+    };
+    if let Err(e) = client.submit_review(&review_id, "COMMENT", None).await {
+        // Nor one holding a thread that was never published
+        client.delete_review(&review_id).await.ok();
+        return Err(e);
+    }
+    Ok(comment)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn new_thread(
+    client: &GhClient,
+    pr_number: u32,
+    path: &str,
+    line: u32,
+    start_line: Option<u32>,
+    side: &str,
+    message: &str,
+    draft: bool,
+    dry_run: bool,
+) -> Result<()> {
+    if let Some(start) = start_line {
+        if start >= line {
+            return Err(crate::error::GhReplyError::InvalidInput(
+                format!("--start-line ({}) must be less than --line ({})", start, line)
+            ));
+        }
+    }
+
+    let target = NewThreadTarget {
+        path: path.to_string(),
+        line,
+        start_line,
+        side: Some(side.to_uppercase()),
+    };
+
+    if draft && dry_run {
+        eprintln!("Dry run mode - would save new thread draft:");
+        eprintln!("PR: {}", pr_number);
+        match start_line {
+            Some(start) => eprintln!("Location: {}:{}-{} ({})", path, start, line, side),
+            None => eprintln!("Location: {}:{} ({})", path, line, side),
+        }
+        eprintln!("Message: {}", message);
+        return Ok(());
+    }
+
+    if draft {
+        let mut store = DraftStore::load().await?;
+        let entry = DraftEntry {
+            body: message.to_string(),
+            path: Some(target.path.clone()),
+            line: Some(target.line),
+            start_line: target.start_line,
+            side: target.side.clone(),
+            original_comment: None,
+            resolve: None,
+            rendered: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        let key = DraftStore::new_thread_key(path, line, &side.to_uppercase());
+        store.add_draft(pr_number, &key, entry);
+        store.save()?;
+
+        eprintln!("Draft saved.");
+        return Ok(());
+    }
+
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    // Expand template variables in the message
+    let context_builder = ContextBuilder::new(client.clone());
+    let context = crate::types::ReplyContext {
+        base: context_builder.build_base_context(&pr_number.to_string()).await?,
+        reply_to: String::new(),
+    };
+    let expander = TemplateExpander::from_context(&context);
+    let expanded_message = expander.expand(message)?;
+
+    if dry_run {
+        eprintln!("Dry run mode - would start a new thread:");
+        eprintln!("PR: {}", pr_number);
+        match start_line {
+            Some(start) => eprintln!("Location: {}:{}-{} ({})", path, start, line, side),
+            None => eprintln!("Location: {}:{} ({})", path, line, side),
+        }
+        eprintln!("Message: {}", expanded_message);
+        return Ok(());
+    }
+
+    let comment = post_new_thread(client, pr_number, &target, &expanded_message).await?;
+
+    output::print(&comment)?;
+    Ok(())
+}
+
+/// Build a suggestion block from the working-tree version of a thread's
+/// commented lines
+pub async fn build_suggestion(thread: &ReviewThread, message: Option<&str>) -> Result<String> {
+    let (start, end) = thread.line_range().ok_or_else(|| crate::error::GhReplyError::InvalidInput(
+        format!("Thread {} is not attached to specific lines", thread.id)
+    ))?;
+    if thread.diff_side.as_deref() == Some("LEFT") {
+        return Err(crate::error::GhReplyError::InvalidInput(
+            format!("Thread {} comments on removed lines; suggestions only apply to the new side", thread.id)
+        ));
+    }
+    let commit = thread.commit_oid.as_deref().ok_or_else(|| crate::error::GhReplyError::InvalidInput(
+        format!("Thread {} has no commit to compare against", thread.id)
+    ))?;
+    git::ensure_commit(commit).await?;
+
+    let hunks = git::diff_hunks(commit, None, &thread.path).await?;
+    let mapped = git::map_range(&hunks, start, end);
+    if !mapped.changed {
+        return Err(crate::error::GhReplyError::InvalidInput(format!(
+            "No local changes to {}:{}-{} since {}",
+            thread.path, start, end, &commit[..commit.len().min(7)]
+        )));
+    }
+    if mapped.extends_outside {
+        return Err(crate::error::GhReplyError::InvalidInput(format!(
+            "Local changes to {} extend beyond lines {}-{}; a suggestion would be incomplete",
+            thread.path, start, end
+        )));
+    }
+
+    let current = git::worktree_file(&thread.path).await?;
+    let lines = if mapped.deleted {
+        Vec::new()
+    } else {
+        git::line_range(&current, mapped.start, mapped.end)
+    };
+
+    Ok(suggestion::format_suggestion(message, &lines))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn suggest(
+    client: &GhClient,
+    pr_number: u32,
+    thread_id: Option<&str>,
+    index: Option<usize>,
+    message: Option<&str>,
+    resolve: bool,
+    draft: bool,
+    dry_run: bool,
+) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    // Fail early on an invalid GHREPLY_RESOLVE value
+    let policy = ResolvePolicy::from_env()?;
+
+    let thread = find_thread(client, pr_number, thread_id, index).await?;
+
+    // Expand template variables in the message only; the suggested code is
+    // sent verbatim, so drafts are stored already rendered
+    let message = match message {
+        Some(message) => {
+            let context = ContextBuilder::new(client.clone())
+                .build_reply_context(&pr_number.to_string(), &thread.id)
+                .await?;
+            Some(TemplateExpander::from_context(&context).expand(message)?)
+        }
+        None => None,
+    };
+
+    let body = build_suggestion(&thread, message.as_deref()).await?;
+
+    if dry_run {
+        eprintln!("Dry run mode - would {}:", if draft { "save suggestion draft" } else { "post suggestion" });
+        eprintln!("PR: {}", pr_number);
+        eprintln!("Thread: {}", thread.id);
+        eprintln!("Message: {}", body);
+        return Ok(());
+    }
+
+    if draft {
+        let mut store = DraftStore::load().await?;
+        let entry = DraftEntry {
+            body,
+            path: Some(thread.path.clone()),
+            line: thread.line,
+            start_line: thread.start_line,
+            side: thread.diff_side.clone(),
+            original_comment: None,
+            resolve: if resolve { Some(true) } else { None },
+            rendered: Some(true),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        store.add_draft(pr_number, &thread.id, entry);
+        store.save()?;
+
+        eprintln!("Draft saved.");
+        return Ok(());
+    }
+
+    let comment = post_suggestion(client, pr_number, &thread.id, &body, resolve, policy).await?;
+
+    output::print(&comment)?;
+    Ok(())
+}
+
+/// Post a suggestion reply, resolving the thread when `resolve` is set.
+/// The resolve policy is checked first, so a thread it would keep open
+/// doesn't get a reply that was meant to close it.
+pub async fn post_suggestion(
+    client: &GhClient,
+    pr_number: u32,
+    thread_id: &str,
+    body: &str,
+    resolve: bool,
+    policy: ResolvePolicy,
+) -> Result<PostedComment> {
+    if resolve {
+        let viewer = if policy.needs_author() {
+            client.get_authenticated_user().await?
+        } else {
+            String::new()
+        };
+        if let ResolveDecision::Skip(_) = resolve_decision(client, policy, thread_id, &viewer).await? {
+            return Err(crate::error::GhReplyError::InvalidInput(format!(
+                "Suggestion not posted: the resolve policy keeps thread {} open; drop --resolve to post it anyway",
+                thread_id
+            )));
+        }
+    }
+
+    let comment = client.post_reply(pr_number, thread_id, body).await?;
+    if resolve {
+        client.resolve_thread(thread_id).await?;
+    }
+    Ok(comment)
+}
+
+/// The suggestion to apply from a thread: the block of the latest comment
+/// holding one, so a reviewer's revised suggestion wins over the original
+fn latest_suggestion(comments: &[ThreadComment]) -> std::result::Result<Vec<String>, String> {
+    let Some(blocks) = comments
+        .iter()
+        .rev()
+        .map(|c| suggestion::parse_suggestions(&c.body))
+        .find(|blocks| !blocks.is_empty())
+    else {
+        return Err("no suggestion block found".to_string());
+    };
+    match <[Vec<String>; 1]>::try_from(blocks) {
+        Ok([block]) => Ok(block),
+        Err(blocks) => Err(format!("{} suggestion blocks found; apply them by hand", blocks.len())),
+    }
+}
+
+/// Apply a suggestion to a thread's lines in the working tree, or with
+/// `dry_run` only check that it applies cleanly.
+/// Returns the replaced line range, or a conflict reason.
+async fn apply_thread_suggestion(
+    thread: &ReviewThread,
+    replacement: &[String],
+    dry_run: bool,
+) -> std::result::Result<(u32, u32), String> {
+    let (start, end) = thread.line_range().ok_or("thread is not attached to specific lines")?;
+    if thread.diff_side.as_deref() == Some("LEFT") {
+        return Err("suggestion targets removed lines".to_string());
+    }
+    let commit = thread.commit_oid.as_deref().ok_or("thread has no commit to compare against")?;
+    git::ensure_commit(commit).await.map_err(|e| e.to_string())?;
+
+    // The suggestion replaces the lines as they were at review time; refuse
+    // when those lines have since changed in the working tree
+    let hunks = git::diff_hunks(commit, None, &thread.path).await.map_err(|e| e.to_string())?;
+    let mapped = git::map_range(&hunks, start, end);
+    let current = git::worktree_file(&thread.path).await.map_err(|e| e.to_string())?;
+    if mapped.changed {
+        if !mapped.deleted && git::line_range(&current, mapped.start, mapped.end) == replacement {
+            return Err("suggestion is already applied".to_string());
+        }
+        return Err(format!(
+            "lines {}-{} of {} changed locally since {}",
+            start, end, thread.path, &commit[..commit.len().min(7)]
+        ));
+    }
+
+    if !dry_run {
+        let updated = suggestion::replace_lines(&current, mapped.start, mapped.end, replacement);
+        let full_path = git::repo_root().await.map_err(|e| e.to_string())?.join(&thread.path);
+        tokio::fs::write(full_path, updated).await.map_err(|e| e.to_string())?;
+    }
+
+    Ok((mapped.start, mapped.end))
+}
+
+pub async fn apply(
+    client: &GhClient,
+    pr_number: u32,
+    targets: &[String],
+    filter: Option<&str>,
+    draft_reply: Option<&str>,
+    resolve: bool,
+    dry_run: bool,
+) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    let output = apply_suggestions(client, pr_number, targets, filter, draft_reply, resolve, dry_run).await?;
+    output::print(&output)?;
     Ok(())
 }
+
+/// Apply the suggestions of the selected threads and return
+/// `{ applied, conflicts }`; with `dry_run` the threads are checked and
+/// reported the same way, but nothing is written
+#[allow(clippy::too_many_arguments)]
+pub async fn apply_suggestions(
+    client: &GhClient,
+    pr_number: u32,
+    targets: &[String],
+    filter: Option<&str>,
+    draft_reply: Option<&str>,
+    resolve: bool,
+    dry_run: bool,
+) -> Result<serde_json::Value> {
+    let filter = parse_selection(targets, filter)?;
+    let threads = client.get_review_threads(pr_number).await?;
+    let thread_ids = select_from_threads(client, pr_number, &threads, targets, filter.as_ref(), true).await?;
+
+    let mut applied = Vec::new();
+    let mut conflicts = Vec::new();
+    for thread_id in &thread_ids {
+        let Some(thread) = threads.iter().find(|t| &t.id == thread_id) else {
+            conflicts.push(serde_json::json!({
+                "threadId": thread_id,
+                "reason": "thread not found",
+            }));
+            continue;
+        };
+
+        // Suggestions may come from any comment of the thread, not just the first
+        let comments = client.get_thread_comments(&thread.id).await?;
+
+        // Apply one thread at a time so each mapping sees the previous edits
+        let result = match latest_suggestion(&comments) {
+            Ok(replacement) => apply_thread_suggestion(thread, &replacement, dry_run).await,
+            Err(reason) => Err(reason),
+        };
+        match result {
+            Ok((start, end)) => {
+                let verb = if dry_run { "Would apply" } else { "Applied" };
+                eprintln!("{} suggestion from thread {} to {}:{}-{}", verb, thread.id, thread.path, start, end);
+                applied.push(serde_json::json!({
+                    "threadId": thread.id,
+                    "path": thread.path,
+                    "startLine": start,
+                    "endLine": end,
+                }));
+            }
+            Err(reason) => {
+                eprintln!("Conflict in thread {}: {}", thread.id, reason);
+                conflicts.push(serde_json::json!({
+                    "threadId": thread.id,
+                    "path": thread.path,
+                    "reason": reason,
+                }));
+            }
+        }
+    }
+
+    // Draft a reply for each applied suggestion; template variables such as
+    // {{short_commit}} are expanded when the drafts are sent after committing
+    if let (Some(template), false) = (draft_reply, dry_run) {
+        let mut store = DraftStore::load().await?;
+        for entry in &applied {
+            let thread_id = entry["threadId"].as_str().unwrap_or_default();
+            let draft = DraftEntry {
+                body: template.to_string(),
+                path: entry["path"].as_str().map(|s| s.to_string()),
+                line: None,
+                start_line: None,
+                side: None,
+                original_comment: None,
+                resolve: if resolve { Some(true) } else { None },
+                rendered: None,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            };
+            store.add_draft(pr_number, thread_id, draft);
+        }
+        store.save()?;
+        if !applied.is_empty() {
+            eprintln!("Drafted {} reply(ies). Commit, then run `draft send {}`.", applied.len(), pr_number);
+        }
+    }
+
+    Ok(serde_json::json!({
+        "applied": applied,
+        "conflicts": conflicts,
+    }))
+}
@@ -1,27 +1,34 @@
-use crate::error::Result;
+use crate::error::{GhReplyError, Result};
+use crate::git;
 use crate::store::DraftStore;
-use crate::types::DraftEntry;
+use crate::trailers;
+use crate::types::{DraftEntry, ReviewThread};
 use crate::github::GhClient;
+use crate::output;
 use crate::context::ContextBuilder;
 use crate::vars::TemplateExpander;
+use crate::policy::{ResolveDecision, ResolvePolicy};
 
 pub async fn add(client: &GhClient, pr_number: u32, thread_id: Option<&str>, index: Option<usize>, message: &str, resolve: bool) -> Result<()> {
     // Ensure gh CLI is available
-    GhClient::ensure_gh_available()?;
+    GhClient::ensure_gh_available().await?;
 
     // Resolve thread identifier to thread ID
     let thread_id = super::comment::resolve_thread_id(client, pr_number, thread_id, index).await?;
 
     // Load draft store
-    let mut store = DraftStore::load()?;
+    let mut store = DraftStore::load().await?;
 
     // Create draft entry
     let draft = DraftEntry {
         body: message.to_string(),
         path: None,
         line: None,
+        start_line: None,
+        side: None,
         original_comment: None,
         resolve: if resolve { Some(true) } else { None },
+        rendered: None,
         timestamp: chrono::Utc::now().to_rfc3339(),
     };
 
@@ -37,28 +44,58 @@ pub async fn add(client: &GhClient, pr_number: u32, thread_id: Option<&str>, ind
 
 pub async fn show(_client: &GhClient, pr_number: u32) -> Result<()> {
     // Load draft store
-    let store = DraftStore::load()?;
+    let store = DraftStore::load().await?;
 
     // Get all drafts for the PR
     let drafts = store.get_all_drafts(pr_number);
 
-    // Output as JSON
+    // Output as a table at the terminal, JSON otherwise
     let output = serde_json::json!({
         "pr_number": pr_number,
         "total": drafts.len(),
         "drafts": drafts,
     });
 
-    println!("{}", serde_json::to_string_pretty(&output)?);
+    output::print_listing(&output)?;
     Ok(())
 }
 
-pub async fn send(client: &GhClient, pr_number: u32, force: bool, dry_run: bool) -> Result<()> {
+/// Resolve a sent draft's thread if the resolve policy allows it, recording
+/// threads left unresolved by the policy
+async fn resolve_sent_thread(
+    client: &GhClient,
+    policy: ResolvePolicy,
+    thread_id: &str,
+    viewer: &str,
+    unresolved: &mut Vec<serde_json::Value>,
+) -> Result<()> {
+    let decision = super::comment::resolve_with_policy(client, policy, thread_id, viewer).await?;
+    if let ResolveDecision::Skip(reason) = decision {
+        unresolved.push(serde_json::json!({
+            "threadId": thread_id,
+            "reason": reason,
+        }));
+    }
+    Ok(())
+}
+
+pub async fn send(
+    client: &GhClient,
+    pr_number: u32,
+    force: bool,
+    dry_run: bool,
+    as_review: bool,
+    summary: Option<&str>,
+    event: &str,
+) -> Result<()> {
     // Ensure gh CLI is available
-    GhClient::ensure_gh_available()?;
+    GhClient::ensure_gh_available().await?;
+
+    // Fail early on an invalid GHREPLY_RESOLVE value
+    let policy = ResolvePolicy::from_env()?;
 
     // Load draft store
-    let mut store = DraftStore::load()?;
+    let mut store = DraftStore::load().await?;
 
     // Get all drafts for the PR
     let drafts = store.get_all_drafts(pr_number);
@@ -68,13 +105,26 @@ pub async fn send(client: &GhClient, pr_number: u32, force: bool, dry_run: bool)
         return Ok(());
     }
 
+    let event = event.to_uppercase();
+
     // Early return for dry run - no need to build context
     if dry_run {
-        eprintln!("Dry run mode - would send {} drafts:", drafts.len());
+        if as_review {
+            eprintln!("Dry run mode - would submit {} drafts as one review ({}):", drafts.len(), event);
+            if let Some(summary) = summary {
+                eprintln!("  Summary: {}", summary);
+            }
+        } else {
+            eprintln!("Dry run mode - would send {} drafts:", drafts.len());
+        }
         for (thread_id, draft) in &drafts {
+            if draft.is_resolve_only() {
+                eprintln!("  Thread {}: (resolve only, resolve policy: {})", thread_id, policy);
+                continue;
+            }
             eprintln!("  Thread {}: {}", thread_id, draft.body);
             if draft.resolve.unwrap_or(false) {
-                eprintln!("    (would resolve)");
+                eprintln!("    (would resolve, resolve policy: {})", policy);
             }
         }
         return Ok(());
@@ -86,47 +136,398 @@ pub async fn send(client: &GhClient, pr_number: u32, force: bool, dry_run: bool)
     // Build base context once (optimization)
     let base_context = context_builder.build_base_context(&pr_number.to_string()).await?;
 
-    // Send each draft
-    for (thread_id, draft) in drafts {
-        // Skip empty bodies unless force is set
-        if !force && draft.body.is_empty() {
-            eprintln!("Skipping empty draft for thread {} (use --force to send)", thread_id);
-            continue;
+    // In review mode every reply is attached to one pending review
+    let review_id = if as_review {
+        let pr_node_id = client.get_pr_node_id(pr_number).await?;
+        Some(client.start_review(&pr_node_id).await?)
+    } else {
+        None
+    };
+
+    // Created comments, and threads whose resolve request was blocked by the policy
+    let mut sent = Vec::new();
+    let mut unresolved = Vec::new();
+
+    // Review mode: PR-level drafts folded into the review body, and threads
+    // to resolve/clear once the review is submitted
+    let mut review_bodies: Vec<String> = summary.map(|s| s.to_string()).into_iter().collect();
+    let mut pending = Vec::new();
+
+    // Send each draft; on failure the comments already posted are still
+    // reported
+    let result: Result<serde_json::Value> = async {
+        for (thread_id, draft) in drafts {
+            // Resolve-only drafts resolve their thread without posting a reply
+            if draft.is_resolve_only() && thread_id != "main" && !DraftStore::is_new_thread_key(&thread_id) {
+                if review_id.is_some() {
+                    pending.push((thread_id, true));
+                    continue;
+                }
+                resolve_sent_thread(client, policy, &thread_id, &base_context.username, &mut unresolved).await?;
+                store.remove_draft(pr_number, &thread_id);
+                store.save()?;
+                continue;
+            }
+
+            // Skip empty bodies unless force is set
+            if !force && draft.body.is_empty() {
+                eprintln!("Skipping empty draft for thread {} (use --force to send)", thread_id);
+                continue;
+            }
+
+            // New thread drafts carry their diff location instead of a thread ID
+            let new_thread = if DraftStore::is_new_thread_key(&thread_id) {
+                let target = draft.new_thread_target().ok_or_else(|| {
+                    crate::error::GhReplyError::StoreError(format!(
+                        "Draft {} is missing its path or line",
+                        thread_id
+                    ))
+                })?;
+                Some(target)
+            } else {
+                None
+            };
+
+            // Get reply-to author
+            let reply_to = if new_thread.is_some() {
+                String::new()
+            } else {
+                context_builder.get_reply_to_author(&thread_id).await?
+            };
+
+            // Build context
+            let context = crate::types::ReplyContext {
+                base: base_context.clone(),
+                reply_to,
+            };
+
+            // Expand template variables, unless the body was rendered when drafted
+            let expanded_message = if draft.rendered.unwrap_or(false) {
+                draft.body.clone()
+            } else {
+                TemplateExpander::from_context(&context).expand(&draft.body)?
+            };
+
+            if let Some(review_id) = &review_id {
+                if thread_id == "main" {
+                    review_bodies.push(expanded_message);
+                } else {
+                    let result = match &new_thread {
+                        Some(target) => client.add_review_thread(review_id, target, &expanded_message).await,
+                        None => client.post_review_reply(review_id, &thread_id, &expanded_message).await,
+                    };
+                    match result {
+                        Ok(comment) => sent.push(comment),
+                        Err(e) => {
+                            // Don't leave a half-filled pending review behind;
+                            // its comments go with it
+                            client.delete_review(review_id).await.ok();
+                            sent.clear();
+                            return Err(e);
+                        }
+                    }
+                }
+                pending.push((thread_id, draft.resolve.unwrap_or(false)));
+                continue;
+            }
+
+            // Post the reply, or start the new thread
+            let comment = match &new_thread {
+                Some(target) => super::comment::post_new_thread(client, pr_number, target, &expanded_message).await?,
+                None => client.post_reply(pr_number, &thread_id, &expanded_message).await?,
+            };
+            sent.push(comment);
+
+            // Remove draft from store and save immediately, so a failure
+            // below doesn't post the reply again on the next send
+            store.remove_draft(pr_number, &thread_id);
+            store.save()?;
+
+            // Resolve thread if requested in draft and allowed by the resolve policy
+            if draft.resolve.unwrap_or(false) && new_thread.is_none() {
+                resolve_sent_thread(client, policy, &thread_id, &base_context.username, &mut unresolved).await?;
+            }
+        }
+
+        let mut review = serde_json::Value::Null;
+        if let Some(review_id) = &review_id {
+            let body = review_bodies.join("\n\n");
+            let summary_expander = TemplateExpander::from_context(&crate::types::ReplyContext {
+                base: base_context.clone(),
+                reply_to: base_context.author.clone(),
+            });
+            let body = summary_expander.expand(&body)?;
+            let body = if body.is_empty() { None } else { Some(body.as_str()) };
+
+            review = match client.submit_review(review_id, &event, body).await {
+                Ok(review) => review,
+                Err(e) => {
+                    client.delete_review(review_id).await.ok();
+                    sent.clear();
+                    return Err(e);
+                }
+            };
+
+            // Clear the drafts once the review is submitted, before resolving,
+            // so a failed resolve doesn't submit the review again on the next send
+            for (thread_id, _) in &pending {
+                store.remove_draft(pr_number, thread_id);
+            }
+            store.save()?;
+
+            // The review is already posted, so a failed resolve is reported
+            // alongside the policy skips instead of aborting the rest
+            for (thread_id, resolve) in pending {
+                if resolve && thread_id != "main" && !DraftStore::is_new_thread_key(&thread_id) {
+                    if let Err(e) = resolve_sent_thread(client, policy, &thread_id, &base_context.username, &mut unresolved).await {
+                        unresolved.push(serde_json::json!({
+                            "threadId": thread_id,
+                            "reason": e.to_string(),
+                        }));
+                    }
+                }
+            }
         }
 
-        // Get reply-to author
-        let reply_to = context_builder.get_reply_to_author(&thread_id).await?;
+        Ok(review)
+    }
+    .await;
+
+    if !unresolved.is_empty() {
+        eprintln!("{} thread(s) left unresolved:", unresolved.len());
+        for entry in &unresolved {
+            eprintln!("  Thread {}: {}", entry["threadId"].as_str().unwrap_or_default(), entry["reason"].as_str().unwrap_or_default());
+        }
+    }
+
+    let mut output = serde_json::json!({
+        "prNumber": pr_number,
+        "sent": sent,
+        "unresolved": unresolved,
+    });
+    let review = match result {
+        Ok(review) => review,
+        Err(e) => {
+            if !sent.is_empty() || !unresolved.is_empty() {
+                output::print(&output)?;
+            }
+            return Err(e);
+        }
+    };
+
+    eprintln!("All replies processed.");
+    if review_id.is_some() {
+        output["review"] = review;
+    }
 
-        // Build context
-        let context = crate::types::ReplyContext {
-            base: base_context.clone(),
-            reply_to,
+    output::print(&output)?;
+    Ok(())
+}
+
+/// Latest commit in `commit_oid..head` that changed the thread's lines.
+/// The line range is followed through every commit that touched the file,
+/// so earlier edits above the thread don't hide a later fix.
+async fn find_fixing_commit(thread: &ReviewThread, head: &str) -> std::result::Result<Option<String>, String> {
+    // Outdated threads lose their line; fall back to the original one and
+    // the commit it refers to
+    let (commit, range) = match thread.line_range() {
+        Some(range) => (thread.commit_oid.as_deref(), Some(range)),
+        None => (thread.original_commit_oid.as_deref(), thread.original_line.map(|l| (l, l))),
+    };
+    let (Some(commit), Some((mut start, mut end))) = (commit, range) else {
+        return Err("thread has no line range or commit".to_string());
+    };
+    if thread.diff_side.as_deref() == Some("LEFT") {
+        return Err("comment on the base side of the diff".to_string());
+    }
+    git::ensure_commit(commit).await.map_err(|e| e.to_string())?;
+
+    let mut fixed = None;
+    for sha in git::commits_touching(commit, head, &thread.path).await.map_err(|e| e.to_string())? {
+        let hunks = git::diff_hunks(&format!("{}^", sha), Some(&sha), &thread.path).await.map_err(|e| e.to_string())?;
+        let mapped = git::map_range(&hunks, start, end);
+        if mapped.changed {
+            fixed = Some(sha);
+        }
+        if mapped.deleted {
+            break;
+        }
+        start = mapped.start;
+        end = mapped.end;
+    }
+    Ok(fixed)
+}
+
+pub async fn suggest_fixed(client: &GhClient, pr_number: u32, message: &str, dry_run: bool) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    let output = fixed_thread_drafts(client, pr_number, message, dry_run).await?;
+    output::print(&output)?;
+    Ok(())
+}
+
+/// Draft a reply for each unresolved thread fixed by a local commit and
+/// return `{ prNumber, drafted, skipped }`; with `dry_run` the same report
+/// is returned without saving the drafts
+pub async fn fixed_thread_drafts(client: &GhClient, pr_number: u32, message: &str, dry_run: bool) -> Result<serde_json::Value> {
+    let head = ContextBuilder::get_local_commit()
+        .await
+        .ok_or_else(|| GhReplyError::GitError("Not inside a git repository with commits".to_string()))?;
+
+    let threads = client.get_review_threads(pr_number).await?;
+    let mut store = DraftStore::load().await?;
+
+    let mut drafted = Vec::new();
+    let mut skipped = Vec::new();
+    for thread in threads.iter().filter(|t| !t.is_resolved) {
+        let fix = match find_fixing_commit(thread, &head).await {
+            Ok(Some(fix)) => fix,
+            Ok(None) => continue,
+            Err(reason) => {
+                skipped.push(serde_json::json!({ "threadId": thread.id, "reason": reason }));
+                continue;
+            }
         };
+        if store.get_draft(pr_number, &thread.id).is_some() {
+            skipped.push(serde_json::json!({ "threadId": thread.id, "reason": "a draft already exists" }));
+            continue;
+        }
 
-        // Expand template variables
-        let expander = TemplateExpander::from_context(&context);
-        let expanded_message = expander.expand(&draft.body)?;
+        // Point the commit variables at the fixing commit; the rest are
+        // expanded when the draft is sent
+        let mut expander = TemplateExpander::new();
+        expander.add_var("local_commit", fix.clone());
+        expander.add_var("short_commit", fix.chars().take(7).collect::<String>());
+        let body = expander.expand_known(message)?;
 
-        // Post the reply
-        client.post_reply(pr_number, &thread_id, &expanded_message).await?;
+        if !dry_run {
+            store.add_draft(pr_number, &thread.id, DraftEntry {
+                body: body.clone(),
+                path: Some(thread.path.clone()),
+                line: None,
+                start_line: None,
+                side: None,
+                original_comment: None,
+                resolve: Some(true),
+                rendered: None,
+                timestamp: chrono::Utc::now().to_rfc3339(),
+            });
+        }
+        drafted.push(serde_json::json!({
+            "threadId": thread.id,
+            "path": thread.path,
+            "commit": fix,
+            "body": body,
+        }));
+    }
+
+    if dry_run {
+        eprintln!("Dry run mode - would draft {} reply(ies)", drafted.len());
+    } else {
+        store.save()?;
+        eprintln!("Drafted {} reply(ies). Review with `draft show {}` and send with `draft send {}`.", drafted.len(), pr_number, pr_number);
+    }
+
+    Ok(serde_json::json!({
+        "prNumber": pr_number,
+        "drafted": drafted,
+        "skipped": skipped,
+    }))
+}
+
+pub async fn from_commits(
+    client: &GhClient,
+    pr_number: u32,
+    range: Option<&str>,
+    message: &str,
+    resolve: bool,
+    dry_run: bool,
+) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    let range = match range {
+        Some(range) => range.to_string(),
+        None => {
+            let details = client.get_pr_details(&pr_number.to_string()).await?;
+            let base = details["baseRefName"].as_str().unwrap_or("main");
+            format!("origin/{}..HEAD", base)
+        }
+    };
+
+    let commits = git::commit_messages(&range).await?;
+    let mut threads = None;
+    let mut store = DraftStore::load().await?;
+    let mut drafted = Vec::new();
+
+    // Oldest first, so a later commit's reply replaces an earlier one
+    for (sha, commit_message) in &commits {
+        for thread_ref in trailers::thread_refs(commit_message) {
+            let thread_id = match thread_ref.thread.parse::<usize>() {
+                Ok(idx) => {
+                    if threads.is_none() {
+                        threads = Some(client.get_review_threads(pr_number).await?);
+                    }
+                    let threads = threads.as_deref().unwrap_or_default();
+                    if idx == 0 || idx > threads.len() {
+                        return Err(GhReplyError::InvalidInput(format!(
+                            "Thread index {} in commit {} is out of range (1-{})",
+                            idx,
+                            &sha[..7.min(sha.len())],
+                            threads.len()
+                        )));
+                    }
+                    threads[idx - 1].id.clone()
+                }
+                Err(_) => thread_ref.thread.clone(),
+            };
 
-        // Resolve thread if requested in draft
-        if draft.resolve.unwrap_or(false) {
-            client.resolve_thread(&thread_id).await?;
+            let mut expander = TemplateExpander::new();
+            expander.add_var("local_commit", sha.clone());
+            expander.add_var("short_commit", sha.chars().take(7).collect::<String>());
+            let body = expander.expand_known(thread_ref.reply.as_deref().unwrap_or(message))?;
+
+            if !dry_run {
+                store.add_draft(pr_number, &thread_id, DraftEntry {
+                    body: body.clone(),
+                    path: None,
+                    line: None,
+                    start_line: None,
+                    side: None,
+                    original_comment: None,
+                    resolve: if resolve { Some(true) } else { None },
+                    rendered: None,
+                    timestamp: chrono::Utc::now().to_rfc3339(),
+                });
+            }
+            drafted.push(serde_json::json!({
+                "threadId": thread_id,
+                "commit": sha,
+                "body": body,
+            }));
         }
+    }
 
-        // Remove draft from store and save immediately
-        store.remove_draft(pr_number, &thread_id);
+    if dry_run {
+        eprintln!("Dry run mode - would draft {} reply(ies) from {} commit(s)", drafted.len(), commits.len());
+    } else {
         store.save()?;
+        eprintln!("Drafted {} reply(ies) from {} commit(s) in {}", drafted.len(), commits.len(), range);
     }
 
-    eprintln!("All replies processed.");
+    let output = serde_json::json!({
+        "prNumber": pr_number,
+        "drafted": drafted,
+    });
+
+    output::print(&output)?;
     Ok(())
 }
 
 pub async fn clear(pr_number: u32) -> Result<()> {
     // Load draft store
-    let mut store = DraftStore::load()?;
+    let mut store = DraftStore::load().await?;
 
     // Clear drafts for the PR
     store.clear_drafts(pr_number);
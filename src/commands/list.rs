@@ -1,5 +1,6 @@
 use crate::error::Result;
 use crate::github::GhClient;
+use crate::output;
 
 pub async fn list(client: &GhClient, state: &str) -> Result<()> {
     // Validate state parameter
@@ -11,12 +12,12 @@ pub async fn list(client: &GhClient, state: &str) -> Result<()> {
     };
 
     // Ensure gh CLI is available
-    GhClient::ensure_gh_available()?;
+    GhClient::ensure_gh_available().await?;
 
     // Execute gh pr list command
     let result = client.list_prs(state_value).await?;
 
-    // Output as JSON
-    println!("{}", serde_json::to_string_pretty(&result)?);
+    // Output as a table at the terminal, JSON otherwise
+    output::print_listing(&result)?;
     Ok(())
 }
@@ -1,6 +1,8 @@
 pub mod comment;
 pub mod draft;
 pub mod list;
+pub mod tui;
+pub mod walk;
 
 use crate::error::Result;
 use crate::github::GhClient;
@@ -15,7 +17,14 @@ pub async fn run_comment_command(client: &GhClient, action: &crate::cli::Comment
             detail,
             page,
             per_page,
+            outdated,
+            no_outdated,
         } => {
+            let outdated = match (*outdated, *no_outdated) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
             comment::list(
                 client,
                 *pr_number,
@@ -25,6 +34,7 @@ pub async fn run_comment_command(client: &GhClient, action: &crate::cli::Comment
                 detail.as_deref(),
                 *page,
                 *per_page,
+                outdated,
             )
             .await
         }
@@ -33,7 +43,20 @@ pub async fn run_comment_command(client: &GhClient, action: &crate::cli::Comment
             thread_id,
             index,
             detail,
-        } => comment::show(client, *pr_number, thread_id.as_deref(), *index, detail.as_deref()).await,
+            with_context,
+            local,
+        } => {
+            comment::show(
+                client,
+                *pr_number,
+                thread_id.as_deref(),
+                *index,
+                detail.as_deref(),
+                *with_context,
+                *local,
+            )
+            .await
+        }
         crate::cli::CommentAction::Reply {
             pr_number,
             thread_id,
@@ -42,6 +65,119 @@ pub async fn run_comment_command(client: &GhClient, action: &crate::cli::Comment
             resolve,
             dry_run,
         } => comment::reply(client, *pr_number, thread_id.as_deref(), *index, message, *resolve, *dry_run).await,
+        crate::cli::CommentAction::Suggest {
+            pr_number,
+            thread_id,
+            index,
+            message,
+            resolve,
+            draft,
+            dry_run,
+        } => {
+            comment::suggest(
+                client,
+                *pr_number,
+                thread_id.as_deref(),
+                *index,
+                message.as_deref(),
+                *resolve,
+                *draft,
+                *dry_run,
+            )
+            .await
+        }
+        crate::cli::CommentAction::Apply {
+            pr_number,
+            targets,
+            filter,
+            draft_reply,
+            resolve,
+            dry_run,
+        } => {
+            comment::apply(
+                client,
+                *pr_number,
+                targets,
+                filter.as_deref(),
+                draft_reply.as_deref(),
+                *resolve,
+                *dry_run,
+            )
+            .await
+        }
+        crate::cli::CommentAction::New {
+            pr_number,
+            path,
+            line,
+            start_line,
+            side,
+            message,
+            draft,
+            dry_run,
+        } => {
+            comment::new_thread(
+                client,
+                *pr_number,
+                path,
+                *line,
+                *start_line,
+                side,
+                message,
+                *draft,
+                *dry_run,
+            )
+            .await
+        }
+        crate::cli::CommentAction::Resolve {
+            pr_number,
+            targets,
+            filter,
+            outdated,
+            reply,
+            dry_run,
+        } => {
+            comment::resolve(
+                client,
+                *pr_number,
+                targets,
+                filter.as_deref(),
+                *outdated,
+                reply.as_deref(),
+                *dry_run,
+            )
+            .await
+        }
+        crate::cli::CommentAction::Unresolve {
+            pr_number,
+            targets,
+            filter,
+            dry_run,
+        } => comment::unresolve(client, *pr_number, targets, filter.as_deref(), *dry_run).await,
+        crate::cli::CommentAction::Edit {
+            comment_id,
+            pr,
+            thread,
+            body,
+            editor,
+            dry_run,
+        } => {
+            comment::edit(
+                client,
+                comment_id.as_deref(),
+                *pr,
+                thread.as_deref(),
+                body.as_deref(),
+                *editor,
+                *dry_run,
+            )
+            .await
+        }
+        crate::cli::CommentAction::Delete {
+            comment_id,
+            pr,
+            thread,
+            dry_run,
+        } => comment::delete(client, comment_id.as_deref(), *pr, thread.as_deref(), *dry_run).await,
         crate::cli::CommentAction::Draft { action } => run_draft_command(client, action).await,
     }
 }
@@ -60,7 +196,33 @@ pub async fn run_draft_command(client: &GhClient, action: &crate::cli::DraftActi
             pr_number,
             force,
             dry_run,
-        } => draft::send(client, *pr_number, *force, *dry_run).await,
+            as_review,
+            summary,
+            event,
+        } => {
+            draft::send(
+                client,
+                *pr_number,
+                *force,
+                *dry_run,
+                *as_review,
+                summary.as_deref(),
+                event,
+            )
+            .await
+        }
+        crate::cli::DraftAction::SuggestFixed {
+            pr_number,
+            message,
+            dry_run,
+        } => draft::suggest_fixed(client, *pr_number, message, *dry_run).await,
+        crate::cli::DraftAction::FromCommits {
+            pr_number,
+            range,
+            message,
+            resolve,
+            dry_run,
+        } => draft::from_commits(client, *pr_number, range.as_deref(), message, *resolve, *dry_run).await,
         crate::cli::DraftAction::Clear { pr_number } => draft::clear(*pr_number).await,
     }
 }
@@ -68,3 +230,11 @@ pub async fn run_draft_command(client: &GhClient, action: &crate::cli::DraftActi
 pub async fn run_list_command(client: &GhClient, state: &str) -> Result<()> {
     list::list(client, state).await
 }
+
+pub async fn run_tui_command(client: &GhClient, pr_number: u32) -> Result<()> {
+    tui::run(client, pr_number).await
+}
+
+pub async fn run_walk_command(client: &GhClient, pr_number: u32, from: Option<&str>, snippets: &[String]) -> Result<()> {
+    walk::run(client, pr_number, from, snippets).await
+}
@@ -0,0 +1,543 @@
+use crate::editor;
+use crate::error::{GhReplyError, Result};
+use crate::github::GhClient;
+use crate::store::DraftStore;
+use crate::types::{DraftEntry, ReviewThread, ThreadComment};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+const HELP: &str = "j/k move  J/K scroll  d draft  r resolve  x discard  s skip  S send  q quit";
+const LEGEND: &str = "✓ resolved  ~ outdated  D draft  + draft & resolve  R resolve only";
+
+/// Conversation and diff hunk of a thread, loaded when it is first selected
+#[derive(Default)]
+struct ThreadDetail {
+    comments: Vec<ThreadComment>,
+    diff_hunk: Option<String>,
+}
+
+/// What the event loop should do after a key press
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    None,
+    EditDraft,
+    Send,
+    Quit,
+}
+
+struct App {
+    pr_number: u32,
+    threads: Vec<ReviewThread>,
+    store: DraftStore,
+    selected: usize,
+    scroll: u16,
+    details: HashMap<String, ThreadDetail>,
+    confirm_send: bool,
+    /// The draft store changed and needs saving
+    dirty: bool,
+    status: String,
+}
+
+impl App {
+    fn new(pr_number: u32, threads: Vec<ReviewThread>, store: DraftStore) -> Self {
+        let selected = threads.iter().position(|t| !t.is_resolved).unwrap_or(0);
+        Self {
+            pr_number,
+            threads,
+            store,
+            selected,
+            scroll: 0,
+            details: HashMap::new(),
+            confirm_send: false,
+            dirty: false,
+            status: String::new(),
+        }
+    }
+
+    fn current(&self) -> Option<&ReviewThread> {
+        self.threads.get(self.selected)
+    }
+
+    fn draft(&self, thread_id: &str) -> Option<&DraftEntry> {
+        self.store.get_draft(self.pr_number, thread_id)
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index.min(self.threads.len().saturating_sub(1));
+        self.scroll = 0;
+    }
+
+    fn handle_key(&mut self, key: KeyCode) -> Action {
+        if self.confirm_send {
+            self.confirm_send = false;
+            if key == KeyCode::Char('y') {
+                return Action::Send;
+            }
+            self.status = "Send cancelled".to_string();
+            return Action::None;
+        }
+
+        self.status.clear();
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::PageDown | KeyCode::Char('J') => self.scroll = self.scroll.saturating_add(5),
+            KeyCode::PageUp | KeyCode::Char('K') => self.scroll = self.scroll.saturating_sub(5),
+            KeyCode::Enter | KeyCode::Char('d') | KeyCode::Char('e') if self.current().is_some() => {
+                return Action::EditDraft;
+            }
+            KeyCode::Char('r') => self.toggle_resolve(),
+            KeyCode::Char('x') => self.discard_draft(),
+            KeyCode::Char('s') => self.skip(),
+            KeyCode::Char('S') => {
+                let count = self.store.get_all_drafts(self.pr_number).len();
+                if count == 0 {
+                    self.status = "No drafts to send".to_string();
+                } else {
+                    self.confirm_send = true;
+                    self.status = format!("Send {} draft(s)? (y/n)", count);
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    /// Toggle resolve-on-send for the selected thread, creating a
+    /// resolve-only draft when there is no reply yet
+    fn toggle_resolve(&mut self) {
+        let Some(thread_id) = self.current().map(|t| t.id.clone()) else {
+            return;
+        };
+        let mut draft = self
+            .draft(&thread_id)
+            .cloned()
+            .unwrap_or_else(|| new_draft(String::new(), false));
+        let resolve = !draft.resolve.unwrap_or(false);
+        draft.resolve = if resolve { Some(true) } else { None };
+
+        if draft.body.trim().is_empty() && !resolve {
+            self.store.remove_draft(self.pr_number, &thread_id);
+        } else {
+            self.store.add_draft(self.pr_number, &thread_id, draft);
+        }
+        self.dirty = true;
+        self.status = if resolve { "Marked for resolve" } else { "Unmarked for resolve" }.to_string();
+    }
+
+    fn discard_draft(&mut self) {
+        let Some(thread_id) = self.current().map(|t| t.id.clone()) else {
+            return;
+        };
+        if self.store.remove_draft(self.pr_number, &thread_id) {
+            self.dirty = true;
+            self.status = "Draft discarded".to_string();
+        }
+    }
+
+    /// Move on to the next unresolved thread without a draft
+    fn skip(&mut self) {
+        let next = (self.selected + 1..self.threads.len())
+            .find(|&i| !self.threads[i].is_resolved && self.draft(&self.threads[i].id).is_none());
+        match next {
+            Some(index) => self.select(index),
+            None => self.status = "No more threads to triage".to_string(),
+        }
+    }
+
+    /// Store the edited reply for the selected thread, keeping its resolve
+    /// mark and the rest of an existing draft
+    fn set_draft_body(&mut self, body: String) {
+        let Some(thread_id) = self.current().map(|t| t.id.clone()) else {
+            return;
+        };
+        let mut draft = self
+            .draft(&thread_id)
+            .cloned()
+            .unwrap_or_else(|| new_draft(String::new(), false));
+        if body.trim().is_empty() && !draft.resolve.unwrap_or(false) {
+            self.store.remove_draft(self.pr_number, &thread_id);
+            self.status = "Empty reply - draft removed".to_string();
+        } else {
+            draft.body = body;
+            draft.timestamp = chrono::Utc::now().to_rfc3339();
+            self.store.add_draft(self.pr_number, &thread_id, draft);
+            self.status = "Draft saved".to_string();
+        }
+        self.dirty = true;
+    }
+
+    /// Fetch the conversation of the selected thread if not loaded yet
+    async fn load_detail(&mut self, client: &GhClient) {
+        let Some(thread_id) = self.current().map(|t| t.id.clone()) else {
+            return;
+        };
+        if self.details.contains_key(&thread_id) {
+            return;
+        }
+
+        let mut detail = ThreadDetail::default();
+        match client.get_thread_comments(&thread_id).await {
+            Ok(comments) => detail.comments = comments,
+            Err(e) => self.status = format!("Failed to load thread: {}", e),
+        }
+        detail.diff_hunk = client.get_thread_diff_hunk(&thread_id).await.ok().flatten();
+        self.details.insert(thread_id, detail);
+    }
+}
+
+fn new_draft(body: String, resolve: bool) -> DraftEntry {
+    DraftEntry {
+        body,
+        path: None,
+        line: None,
+        start_line: None,
+        side: None,
+        original_comment: None,
+        resolve: if resolve { Some(true) } else { None },
+        rendered: None,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+/// Marker column for the thread list: resolved, outdated and draft state
+fn markers(thread: &ReviewThread, draft: Option<&DraftEntry>) -> String {
+    let draft_marker = match draft {
+        Some(d) if d.is_resolve_only() => 'R',
+        Some(d) if d.resolve.unwrap_or(false) => '+',
+        Some(_) => 'D',
+        None => ' ',
+    };
+    format!(
+        "{}{}{}",
+        if thread.is_resolved { '✓' } else { ' ' },
+        if thread.is_outdated { '~' } else { ' ' },
+        draft_marker
+    )
+}
+
+fn location(thread: &ReviewThread) -> String {
+    match thread.line.or(thread.original_line) {
+        Some(line) => format!("{}:{}", thread.path, line),
+        None => thread.path.clone(),
+    }
+}
+
+fn detail_lines<'a>(app: &'a App, thread: &'a ReviewThread) -> Vec<Line<'a>> {
+    let bold = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::styled(location(thread), bold)];
+
+    let mut state = vec![if thread.is_resolved { "resolved" } else { "unresolved" }];
+    if thread.is_outdated {
+        state.push("outdated");
+    }
+    if thread.subject_type.as_deref() == Some("FILE") {
+        state.push("file comment");
+    }
+    lines.push(Line::raw(state.join(", ")));
+    lines.push(Line::raw(""));
+
+    let detail = app.details.get(&thread.id);
+    if let Some(hunk) = detail.and_then(|d| d.diff_hunk.as_deref()) {
+        for line in hunk.lines() {
+            let color = match line.chars().next() {
+                Some('+') => Color::Green,
+                Some('-') => Color::Red,
+                Some('@') => Color::Cyan,
+                _ => Color::Reset,
+            };
+            lines.push(Line::styled(line, Style::default().fg(color)));
+        }
+        lines.push(Line::raw(""));
+    }
+
+    let author_style = bold.fg(Color::Yellow);
+    match detail.filter(|d| !d.comments.is_empty()) {
+        Some(detail) => {
+            for comment in &detail.comments {
+                lines.push(Line::from(vec![
+                    Span::styled(comment.author.as_deref().unwrap_or("ghost"), author_style),
+                    Span::raw(format!("  {}", comment.created_at)),
+                ]));
+                lines.extend(comment.body.lines().map(Line::raw));
+                lines.push(Line::raw(""));
+            }
+        }
+        None => {
+            lines.push(Line::styled(thread.author.as_deref().unwrap_or("ghost"), author_style));
+            lines.extend(thread.body.lines().map(Line::raw));
+            lines.push(Line::raw(""));
+        }
+    }
+
+    if let Some(draft) = app.draft(&thread.id) {
+        let title = if draft.resolve.unwrap_or(false) { "Draft (resolve on send)" } else { "Draft" };
+        lines.push(Line::styled(title, bold.fg(Color::Magenta)));
+        if draft.is_resolve_only() {
+            lines.push(Line::raw("(resolve only)"));
+        } else {
+            lines.extend(draft.body.lines().map(Line::raw));
+        }
+    }
+    lines
+}
+
+fn draw(frame: &mut Frame, app: &App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(2)])
+        .split(frame.area());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+        .split(rows[0]);
+
+    let items: Vec<ListItem> = app
+        .threads
+        .iter()
+        .enumerate()
+        .map(|(i, thread)| {
+            let text = format!("{} {:>2} {}", markers(thread, app.draft(&thread.id)), i + 1, location(thread));
+            let style = if thread.is_resolved {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            ListItem::new(text).style(style)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(format!(" PR #{} ", app.pr_number)))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, columns[0], &mut list_state);
+
+    let detail = match app.current() {
+        Some(thread) => Paragraph::new(detail_lines(app, thread)),
+        None => Paragraph::new("No review threads"),
+    };
+    frame.render_widget(
+        detail
+            .block(Block::default().borders(Borders::ALL))
+            .wrap(Wrap { trim: false })
+            .scroll((app.scroll, 0)),
+        columns[1],
+    );
+
+    let status = if app.status.is_empty() { LEGEND } else { app.status.as_str() };
+    frame.render_widget(
+        Paragraph::new(vec![Line::raw(HELP), Line::styled(status, Style::default().fg(Color::Cyan))]),
+        rows[1],
+    );
+}
+
+/// Leave the alternate screen while an external editor runs
+async fn edit_outside(terminal: &mut DefaultTerminal, initial: &str) -> Result<Result<String>> {
+    disable_raw_mode()?;
+    execute!(std::io::stdout(), LeaveAlternateScreen)?;
+    let edited = editor::edit_text(initial).await;
+    execute!(std::io::stdout(), EnterAlternateScreen)?;
+    enable_raw_mode()?;
+    terminal.clear()?;
+    Ok(edited)
+}
+
+/// Run the UI until the user quits; returns whether drafts should be sent
+async fn event_loop(terminal: &mut DefaultTerminal, client: &GhClient, app: &mut App) -> Result<bool> {
+    loop {
+        app.load_detail(client).await;
+        terminal.draw(|frame| draw(frame, app))?;
+
+        // Wait for input off the runtime workers
+        let event = tokio::task::spawn_blocking(event::read)
+            .await
+            .map_err(|e| GhReplyError::Other(e.into()))??;
+        let Event::Key(key) = event else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Ok(false);
+        }
+
+        match app.handle_key(key.code) {
+            Action::None => {}
+            Action::Quit => return Ok(false),
+            Action::Send => return Ok(true),
+            Action::EditDraft => {
+                let initial = app
+                    .current()
+                    .and_then(|t| app.draft(&t.id))
+                    .map(|d| d.body.clone())
+                    .unwrap_or_default();
+                match edit_outside(terminal, &initial).await? {
+                    Ok(body) => app.set_draft_body(body),
+                    Err(e) => app.status = e.to_string(),
+                }
+            }
+        }
+
+        // Save after every change so the CLI sees the same drafts
+        if app.dirty {
+            app.store.save()?;
+            app.dirty = false;
+        }
+    }
+}
+
+pub async fn run(client: &GhClient, pr_number: u32) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(GhReplyError::InvalidInput(
+            "gh-reply tui needs an interactive terminal".to_string(),
+        ));
+    }
+
+    let threads = client.get_review_threads(pr_number).await?;
+    let store = DraftStore::load().await?;
+    let mut app = App::new(pr_number, threads, store);
+
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, client, &mut app).await;
+    ratatui::restore();
+
+    if result? {
+        super::draft::send(client, pr_number, false, false, false, None, "COMMENT").await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(id: &str, resolved: bool) -> ReviewThread {
+        ReviewThread {
+            id: id.to_string(),
+            path: "src/main.rs".to_string(),
+            line: Some(3),
+            start_line: None,
+            diff_side: None,
+            body: "Please rename".to_string(),
+            is_resolved: resolved,
+            is_outdated: false,
+            original_line: Some(3),
+            original_start_line: None,
+            subject_type: Some("LINE".to_string()),
+            author: Some("reviewer".to_string()),
+            last_author: Some("reviewer".to_string()),
+            commit_oid: None,
+            original_commit_oid: None,
+            current_line: None,
+            line_status: None,
+        }
+    }
+
+    fn app() -> App {
+        let threads = vec![thread("T1", true), thread("T2", false), thread("T3", false), thread("T4", false)];
+        App::new(1, threads, DraftStore::default())
+    }
+
+    #[test]
+    fn test_starts_on_first_unresolved_thread() {
+        let mut app = app();
+        assert_eq!(app.selected, 1);
+        app.handle_key(KeyCode::Char('k'));
+        app.handle_key(KeyCode::Char('k'));
+        assert_eq!(app.selected, 0);
+        for _ in 0..10 {
+            app.handle_key(KeyCode::Down);
+        }
+        assert_eq!(app.selected, 3);
+    }
+
+    #[test]
+    fn test_toggle_resolve_creates_resolve_only_draft() {
+        let mut app = app();
+        app.handle_key(KeyCode::Char('r'));
+        assert!(app.draft("T2").unwrap().is_resolve_only());
+        assert!(app.dirty);
+
+        app.handle_key(KeyCode::Char('r'));
+        assert!(app.draft("T2").is_none());
+    }
+
+    #[test]
+    fn test_edited_body_keeps_resolve_mark() {
+        let mut app = app();
+        app.handle_key(KeyCode::Char('r'));
+        app.set_draft_body("Done".to_string());
+        let draft = app.draft("T2").unwrap();
+        assert_eq!(draft.body, "Done");
+        assert_eq!(draft.resolve, Some(true));
+        assert_eq!(markers(&app.threads[1], Some(draft)), "  +");
+    }
+
+    #[test]
+    fn test_edited_body_keeps_draft_location() {
+        let mut app = app();
+        let mut draft = new_draft("Suggested".to_string(), false);
+        draft.path = Some("src/main.rs".to_string());
+        draft.line = Some(3);
+        draft.side = Some("RIGHT".to_string());
+        app.store.add_draft(1, "T2", draft);
+
+        app.set_draft_body("Suggested, with a note".to_string());
+        let draft = app.draft("T2").unwrap();
+        assert_eq!(draft.body, "Suggested, with a note");
+        assert_eq!((draft.path.as_deref(), draft.line, draft.side.as_deref()), (Some("src/main.rs"), Some(3), Some("RIGHT")));
+    }
+
+    #[test]
+    fn test_skip_passes_drafted_threads() {
+        let mut app = app();
+        app.select(2);
+        app.set_draft_body("Done".to_string());
+        app.select(1);
+        app.handle_key(KeyCode::Char('s'));
+        assert_eq!(app.selected, 3);
+        app.handle_key(KeyCode::Char('s'));
+        assert_eq!(app.status, "No more threads to triage");
+    }
+
+    #[test]
+    fn test_draw_shows_markers_and_draft() {
+        use ratatui::backend::TestBackend;
+
+        let mut app = app();
+        app.set_draft_body("Renamed".to_string());
+        let mut terminal = ratatui::Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, &app)).unwrap();
+
+        let screen: String = terminal.backend().buffer().content().iter().map(|c| c.symbol()).collect();
+        assert!(screen.contains("✓    1 src/main.rs:3"));
+        assert!(screen.contains("D  2 src/main.rs:3"));
+        assert!(screen.contains("Renamed"));
+    }
+
+    #[test]
+    fn test_send_needs_confirmation() {
+        let mut app = app();
+        app.handle_key(KeyCode::Char('S'));
+        assert_eq!(app.status, "No drafts to send");
+
+        app.set_draft_body("Done".to_string());
+        assert_eq!(app.handle_key(KeyCode::Char('S')), Action::None);
+        assert_eq!(app.handle_key(KeyCode::Char('n')), Action::None);
+        app.handle_key(KeyCode::Char('S'));
+        assert_eq!(app.handle_key(KeyCode::Char('y')), Action::Send);
+    }
+}
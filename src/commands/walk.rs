@@ -0,0 +1,351 @@
+use crate::context::ContextBuilder;
+use crate::error::{GhReplyError, Result};
+use crate::github::GhClient;
+use crate::store::DraftStore;
+use crate::types::{DraftEntry, ReplyContext, ReviewThread};
+use crate::vars::TemplateExpander;
+use std::io::{BufRead, Write};
+
+/// Canned replies offered by the snippet prompt when none are given
+pub const DEFAULT_SNIPPETS: &[&str] = &[
+    "Fixed in {{short_commit}}.",
+    "Thanks @{{reply_to}}, addressed in {{short_commit}}.",
+    "This is intentional - see the PR description.",
+];
+
+/// Counts of the answers given during a walk
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct WalkSummary {
+    pub replied: usize,
+    pub resolve_only: usize,
+    pub skipped: usize,
+    /// The walk ended early with `q` or end of input
+    pub quit: bool,
+}
+
+/// Print `text` and read one trimmed line; None at end of input
+fn prompt<R: BufRead, W: Write>(input: &mut R, output: &mut W, text: &str) -> Result<Option<String>> {
+    write!(output, "{}", text)?;
+    output.flush()?;
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line.trim().to_string()))
+}
+
+/// Read a multi-line reply ended by a line with a lone `.` or end of input,
+/// so replies can contain blank lines
+fn read_reply<R: BufRead, W: Write>(input: &mut R, output: &mut W) -> Result<String> {
+    writeln!(output, "Reply (end with a line containing only '.'):")?;
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 || line.trim() == "." {
+            break;
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    Ok(lines.join("\n").trim_matches('\n').to_string())
+}
+
+fn confirm<R: BufRead, W: Write>(input: &mut R, output: &mut W, question: &str) -> Result<bool> {
+    let answer = prompt(input, output, &format!("{} [y/N] ", question))?;
+    Ok(matches!(answer.as_deref(), Some("y") | Some("Y") | Some("yes")))
+}
+
+fn draft_entry(thread: &ReviewThread, body: String, resolve: bool) -> DraftEntry {
+    DraftEntry {
+        body,
+        path: Some(thread.path.clone()),
+        line: None,
+        start_line: None,
+        side: None,
+        original_comment: None,
+        resolve: if resolve { Some(true) } else { None },
+        rendered: None,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+fn print_thread<W: Write>(output: &mut W, position: usize, total: usize, thread: &ReviewThread, existing: Option<&DraftEntry>) -> Result<()> {
+    let line = match thread.line.or(thread.original_line) {
+        Some(line) => format!(":{}", line),
+        None => String::new(),
+    };
+    let outdated = if thread.is_outdated { " (outdated)" } else { "" };
+    writeln!(output)?;
+    writeln!(output, "[{}/{}] {}{}{}", position, total, thread.path, line, outdated)?;
+    writeln!(output, "{}:", thread.author.as_deref().unwrap_or("ghost"))?;
+    for body_line in thread.body.lines() {
+        writeln!(output, "  {}", body_line)?;
+    }
+    if let Some(draft) = existing {
+        let body = if draft.is_resolve_only() { "(resolve only)" } else { draft.body.as_str() };
+        writeln!(output, "Existing draft: {}", body)?;
+    }
+    Ok(())
+}
+
+/// Step through `threads`, asking for an answer to each and storing it in
+/// `store`. `preview` expands a reply for display; `persist` is called after
+/// every change so no answer is lost if the walk is interrupted.
+#[allow(clippy::too_many_arguments)]
+pub fn walk_threads<R, W, P, S>(
+    input: &mut R,
+    output: &mut W,
+    pr_number: u32,
+    threads: &[ReviewThread],
+    store: &mut DraftStore,
+    snippets: &[String],
+    preview: P,
+    mut persist: S,
+) -> Result<WalkSummary>
+where
+    R: BufRead,
+    W: Write,
+    P: Fn(&ReviewThread, &str) -> String,
+    S: FnMut(&DraftStore) -> Result<()>,
+{
+    let mut summary = WalkSummary::default();
+
+    for (i, thread) in threads.iter().enumerate() {
+        print_thread(output, i + 1, threads.len(), thread, store.get_draft(pr_number, &thread.id))?;
+
+        loop {
+            let Some(answer) = prompt(input, output, "[r]eply  [t] snippet  [o] resolve only  [s]kip  [q]uit > ")? else {
+                summary.quit = true;
+                return Ok(summary);
+            };
+
+            let body = match answer.as_str() {
+                "r" => read_reply(input, output)?,
+                "t" => {
+                    for (n, snippet) in snippets.iter().enumerate() {
+                        writeln!(output, "  {}) {}", n + 1, preview(thread, snippet))?;
+                    }
+                    let choice = prompt(input, output, "Snippet number > ")?;
+                    match choice.and_then(|c| c.parse::<usize>().ok()).and_then(|n| snippets.get(n.wrapping_sub(1))) {
+                        Some(snippet) => snippet.clone(),
+                        None => {
+                            writeln!(output, "No such snippet")?;
+                            continue;
+                        }
+                    }
+                }
+                "o" => {
+                    store.add_draft(pr_number, &thread.id, draft_entry(thread, String::new(), true));
+                    persist(store)?;
+                    summary.resolve_only += 1;
+                    break;
+                }
+                "s" | "" => {
+                    summary.skipped += 1;
+                    break;
+                }
+                "q" => {
+                    summary.quit = true;
+                    return Ok(summary);
+                }
+                other => {
+                    writeln!(output, "Unknown answer '{}'", other)?;
+                    continue;
+                }
+            };
+
+            if body.trim().is_empty() {
+                writeln!(output, "Empty reply - nothing saved")?;
+                continue;
+            }
+            writeln!(output, "Preview: {}", preview(thread, &body))?;
+            let resolve = confirm(input, output, "Resolve on send?")?;
+            store.add_draft(pr_number, &thread.id, draft_entry(thread, body, resolve));
+            persist(store)?;
+            summary.replied += 1;
+            break;
+        }
+    }
+
+    Ok(summary)
+}
+
+pub async fn run(client: &GhClient, pr_number: u32, from: Option<&str>, snippets: &[String]) -> Result<()> {
+    // Ensure gh CLI is available
+    GhClient::ensure_gh_available().await?;
+
+    let threads = client.get_review_threads(pr_number).await?;
+
+    // Start at the given thread, skipping the ones before it
+    let start = match from {
+        Some(from) => {
+            let (thread_id, index) = match from.parse::<usize>() {
+                Ok(index) => (None, Some(index)),
+                Err(_) => (Some(from), None),
+            };
+            let thread_id = super::comment::resolve_thread_id(client, pr_number, thread_id, index).await?;
+            match threads.iter().position(|t| t.id == thread_id) {
+                Some(start) => start,
+                None => {
+                    let location = client.pr_location(pr_number).await;
+                    return Err(GhReplyError::not_found("Thread", thread_id.as_str(), Some(location)));
+                }
+            }
+        }
+        None => 0,
+    };
+    let pending: Vec<ReviewThread> = threads[start..].iter().filter(|t| !t.is_resolved).cloned().collect();
+    if pending.is_empty() {
+        eprintln!("No unresolved threads in PR #{}", pr_number);
+        return Ok(());
+    }
+
+    let snippets: Vec<String> = if snippets.is_empty() {
+        DEFAULT_SNIPPETS.iter().map(|s| s.to_string()).collect()
+    } else {
+        snippets.to_vec()
+    };
+
+    // Previews use the same variables as `draft send`
+    let base = ContextBuilder::new(client.clone()).build_base_context(&pr_number.to_string()).await?;
+    let preview = move |thread: &ReviewThread, body: &str| {
+        let context = ReplyContext {
+            base: base.clone(),
+            reply_to: thread.author.clone().unwrap_or_default(),
+        };
+        TemplateExpander::from_context(&context)
+            .expand(body)
+            .unwrap_or_else(|_| body.to_string())
+    };
+
+    let mut store = DraftStore::load().await?;
+
+    // The prompts block on stdin, so keep them off the async runtime
+    let send = tokio::task::spawn_blocking(move || -> Result<bool> {
+        let mut stdin = std::io::stdin().lock();
+        let mut stdout = std::io::stdout();
+        let summary = walk_threads(
+            &mut stdin,
+            &mut stdout,
+            pr_number,
+            &pending,
+            &mut store,
+            &snippets,
+            preview,
+            |store| store.save(),
+        )?;
+
+        let drafts = store.get_all_drafts(pr_number).len();
+        println!();
+        println!(
+            "Drafted {} reply(ies) and {} resolve-only, skipped {}. {} draft(s) pending for PR #{}.",
+            summary.replied, summary.resolve_only, summary.skipped, drafts, pr_number
+        );
+
+        Ok(drafts > 0 && confirm(&mut stdin, &mut stdout, "Send them now with `draft send`?")?)
+    })
+    .await
+    .map_err(|e| GhReplyError::Other(e.into()))??;
+
+    if send {
+        super::draft::send(client, pr_number, false, false, false, None, "COMMENT").await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(id: &str) -> ReviewThread {
+        ReviewThread {
+            id: id.to_string(),
+            path: "src/main.rs".to_string(),
+            line: Some(10),
+            start_line: None,
+            diff_side: None,
+            body: "Please rename this".to_string(),
+            is_resolved: false,
+            is_outdated: false,
+            original_line: Some(10),
+            original_start_line: None,
+            subject_type: Some("LINE".to_string()),
+            author: Some("reviewer".to_string()),
+            last_author: Some("reviewer".to_string()),
+            commit_oid: None,
+            original_commit_oid: None,
+            current_line: None,
+            line_status: None,
+        }
+    }
+
+    fn walk(answers: &str, threads: &[ReviewThread], store: &mut DraftStore) -> (WalkSummary, String) {
+        let snippets = vec!["Fixed in {{short_commit}}.".to_string()];
+        let mut output = Vec::new();
+        let mut saves = 0;
+        let summary = walk_threads(
+            &mut answers.as_bytes(),
+            &mut output,
+            1,
+            threads,
+            store,
+            &snippets,
+            |_, body| body.replace("{{short_commit}}", "abc1234"),
+            |_| {
+                saves += 1;
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(saves, summary.replied + summary.resolve_only);
+        (summary, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_walk_reply_snippet_and_resolve_only() {
+        let threads = vec![thread("T1"), thread("T2"), thread("T3"), thread("T4")];
+        let mut store = DraftStore::default();
+        let answers = "r\nRenamed.\n\nSee below.\n.\ny\nt\n1\nn\no\ns\n";
+        let (summary, output) = walk(answers, &threads, &mut store);
+
+        assert_eq!(summary, WalkSummary { replied: 2, resolve_only: 1, skipped: 1, quit: false });
+        let t1 = store.get_draft(1, "T1").unwrap();
+        assert_eq!(t1.body, "Renamed.\n\nSee below.");
+        assert_eq!(t1.resolve, Some(true));
+        assert_eq!(store.get_draft(1, "T2").unwrap().body, "Fixed in {{short_commit}}.");
+        assert!(store.get_draft(1, "T3").unwrap().is_resolve_only());
+        assert!(store.get_draft(1, "T4").is_none());
+        assert!(output.contains("1) Fixed in abc1234."));
+        assert!(output.contains("[4/4] src/main.rs:10"));
+    }
+
+    #[test]
+    fn test_walk_reprompts_and_quits() {
+        let threads = vec![thread("T1"), thread("T2")];
+        let mut store = DraftStore::default();
+        let (summary, output) = walk("x\nt\n9\nq\n", &threads, &mut store);
+
+        assert!(summary.quit);
+        assert!(output.contains("Unknown answer 'x'"));
+        assert!(output.contains("No such snippet"));
+        assert!(!output.contains("[2/2]"));
+    }
+
+    #[test]
+    fn test_walk_reply_ends_at_end_of_input() {
+        let threads = vec![thread("T1")];
+        let mut store = DraftStore::default();
+        let (summary, _) = walk("r\nLast words\n", &threads, &mut store);
+
+        // The reply is kept; the resolve question then hits end of input
+        assert_eq!(summary.replied, 1);
+        assert_eq!(store.get_draft(1, "T1").unwrap().body, "Last words");
+    }
+
+    #[test]
+    fn test_walk_stops_at_end_of_input() {
+        let threads = vec![thread("T1")];
+        let mut store = DraftStore::default();
+        let (summary, _) = walk("", &threads, &mut store);
+        assert!(summary.quit);
+    }
+}
@@ -15,17 +15,18 @@ impl ContextBuilder {
         &self,
         pr_number: &str,
     ) -> Result<BaseReplyContext> {
-        // Get repository information
-        let (repo_owner, repo_name) = self.client.get_repo_info()?;
-
-        // Get PR details
-        let pr_details = self.client.get_pr_details(pr_number)?;
-
-        // Get authenticated user
-        let auth_user = self.client.get_authenticated_user()?;
-
-        // Get local commit hash
-        let local_commit = Self::get_local_commit().await.unwrap_or_default();
+        // Repository, PR details, authenticated user and local commit are
+        // independent lookups; run them concurrently
+        let (repo_info, pr_details, auth_user, local_commit) = tokio::join!(
+            self.client.get_repo_info(),
+            self.client.get_pr_details(pr_number),
+            self.client.get_authenticated_user(),
+            Self::get_local_commit(),
+        );
+        let (repo_owner, repo_name) = repo_info?;
+        let pr_details = pr_details?;
+        let auth_user = auth_user?;
+        let local_commit = local_commit.unwrap_or_default();
 
         // Determine username with fallback
         let username = if !auth_user.is_empty() {
@@ -49,11 +50,12 @@ impl ContextBuilder {
         })
     }
 
-    async fn get_local_commit() -> Option<String> {
+    /// Full SHA of local HEAD, if inside a git repository
+    pub async fn get_local_commit() -> Option<String> {
         use tokio::process::Command;
 
         let output = Command::new("git")
-            .args(&["rev-parse", "HEAD"])
+            .args(["rev-parse", "HEAD"])
             .output()
             .await
             .ok()?;
@@ -98,7 +100,7 @@ impl ContextBuilder {
                     .and_then(|n| n.get("comments"))
                     .and_then(|c| c.get("nodes"))
                     .and_then(|nodes| nodes.as_array())
-                    .and_then(|nodes| nodes.get(0))
+                    .and_then(|nodes| nodes.first())
                     .and_then(|comment| comment.get("author"))
                     .and_then(|author| author.get("login"))
                     .and_then(|login| login.as_str());
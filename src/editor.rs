@@ -0,0 +1,40 @@
+use crate::error::{GhReplyError, Result};
+use std::fs;
+use tokio::process::Command;
+
+/// Open `$VISUAL`/`$EDITOR` (falling back to `vi`) on a temporary file
+/// pre-filled with `initial`, and return the edited text.
+pub async fn edit_text(initial: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    let path = std::env::temp_dir().join(format!(
+        "gh-reply-{}-{}.md",
+        std::process::id(),
+        rand::random::<u32>()
+    ));
+    fs::write(&path, initial)?;
+
+    // The editor value may carry arguments (e.g. "code --wait")
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .await
+        .map_err(|e| GhReplyError::ConfigError(format!("Failed to launch editor '{}': {}", editor, e)));
+
+    let result = match status {
+        Ok(status) if status.success() => fs::read_to_string(&path).map_err(GhReplyError::from),
+        Ok(status) => Err(GhReplyError::ConfigError(format!(
+            "Editor '{}' exited with {}",
+            editor, status
+        ))),
+        Err(e) => Err(e),
+    };
+
+    fs::remove_file(&path).ok();
+    Ok(result?.trim_end().to_string())
+}
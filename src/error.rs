@@ -1,3 +1,4 @@
+use serde_json::Value;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,12 +6,69 @@ pub enum GhReplyError {
     #[error("GitHub CLI error: {0}")]
     GhError(String),
 
+    #[error("`gh` CLI is not installed or not in PATH. Install GitHub CLI: https://cli.github.com/")]
+    GhNotInstalled,
+
+    #[error("`gh` is not authenticated: {0}. Run `gh auth login` to authenticate.")]
+    AuthError(String),
+
+    #[error("{resource} {id} not found{}", .location.as_ref().map(|l| format!(" on {}", l)).unwrap_or_default())]
+    NotFound {
+        resource: String,
+        id: String,
+        location: Option<String>,
+    },
+
+    #[error("Thread index {index} is out of range (1-{total})")]
+    IndexOutOfRange { index: usize, total: usize },
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("GraphQL error{}: {message}{}", .path.as_ref().map(|p| format!(" at {}", p)).unwrap_or_default(), .kind.hint())]
+    GraphqlError {
+        kind: GraphqlErrorKind,
+        message: String,
+        /// Dotted path of the failing field, e.g. `repository.pullRequest`
+        path: Option<String>,
+        errors: Vec<Value>,
+    },
+
+    /// A gh request that failed without a GraphQL `errors` array, classified
+    /// from gh's stderr: HTTP 404/403, HTTP 5xx or a network failure
+    #[error("GitHub request failed{}: {message}{}", .status.map(|s| format!(" (HTTP {})", s)).unwrap_or_default(), .kind.hint())]
+    HttpError {
+        kind: GraphqlErrorKind,
+        /// HTTP status, `None` for network failures
+        status: Option<u16>,
+        message: String,
+    },
+
+    #[error("{message}")]
+    RateLimited {
+        message: String,
+        /// Seconds GitHub asked to wait before retrying
+        retry_after: Option<u64>,
+    },
+
+    #[error("gh {command} did not respond within {} and was stopped", crate::github::policy::format_duration(.timeout))]
+    Timeout {
+        command: String,
+        timeout: std::time::Duration,
+    },
+
     #[error("Store error: {0}")]
     StoreError(String),
 
     #[error("Template error: {0}")]
     TemplateError(String),
 
+    #[error("Configuration error: {0}")]
+    ConfigError(String),
+
+    #[error("Git error: {0}")]
+    GitError(String),
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -21,4 +79,303 @@ pub enum GhReplyError {
     Other(#[from] anyhow::Error),
 }
 
+/// Classification of the errors a GraphQL response carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphqlErrorKind {
+    NotFound,
+    Forbidden,
+    RateLimited,
+    /// The query itself was rejected (unknown field, bad argument, ...)
+    Validation,
+    Server,
+    /// GitHub could not be reached (gh requests only)
+    Network,
+    Other,
+}
+
+impl GraphqlErrorKind {
+    /// Classify one entry of a GraphQL `errors` array by its `type`, falling
+    /// back to the message for errors GitHub reports without one
+    pub fn classify(error: &Value) -> Self {
+        let message = error["message"].as_str().unwrap_or_default().to_lowercase();
+        match error["type"].as_str().unwrap_or_default() {
+            "NOT_FOUND" => Self::NotFound,
+            "FORBIDDEN" | "INSUFFICIENT_SCOPES" => Self::Forbidden,
+            "RATE_LIMITED" => Self::RateLimited,
+            "INTERNAL" | "SERVICE_UNAVAILABLE" | "TIMEOUT" => Self::Server,
+            _ if message.contains("rate limit") => Self::RateLimited,
+            _ if message.contains("could not resolve to") => Self::NotFound,
+            _ if message.contains("not accessible") => Self::Forbidden,
+            _ if message.contains("something went wrong") || message.contains("timeout") => Self::Server,
+            _ if error["extensions"]["code"].is_string() => Self::Validation,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::NotFound => "not_found",
+            Self::Forbidden => "forbidden",
+            Self::RateLimited => "rate_limited",
+            Self::Validation => "validation",
+            Self::Server => "server",
+            Self::Network => "network",
+            Self::Other => "other",
+        }
+    }
+
+    /// Whether retrying the same request may succeed
+    pub fn is_transient(self) -> bool {
+        matches!(self, Self::RateLimited | Self::Server | Self::Network)
+    }
+
+    /// What the user can do about the error, appended to its message
+    fn hint(self) -> &'static str {
+        match self {
+            Self::Forbidden => " (check that your gh token can access the repository, e.g. `gh auth refresh -s repo`)",
+            Self::RateLimited => " (GitHub API rate limit exceeded; wait and try again)",
+            Self::Validation => " (GitHub rejected the query; please report this as a gh-reply bug)",
+            Self::Server => " (GitHub had a server error; try again later)",
+            Self::Network => " (could not reach GitHub; check your connection and try again)",
+            Self::NotFound | Self::Other => "",
+        }
+    }
+}
+
+impl GhReplyError {
+    /// A `not found` error for `resource` `id`, optionally located in `location`
+    pub fn not_found(resource: &str, id: impl Into<String>, location: Option<String>) -> Self {
+        Self::NotFound {
+            resource: resource.to_string(),
+            id: id.into(),
+            location,
+        }
+    }
+
+    /// Replace a GraphQL NOT_FOUND with a `not found` error naming what was
+    /// looked up; `location` is only computed when needed
+    pub fn with_not_found(self, resource: &str, id: &str, location: impl FnOnce() -> Option<String>) -> Self {
+        if self.is_graphql_not_found() {
+            return Self::not_found(resource, id, location());
+        }
+        self
+    }
+
+    /// Whether GraphQL reported that the looked-up node does not exist
+    pub fn is_graphql_not_found(&self) -> bool {
+        matches!(self, Self::GraphqlError { kind: GraphqlErrorKind::NotFound, .. })
+    }
+
+    /// Whether the failed request may succeed when retried: rate limits,
+    /// server errors (GraphQL or HTTP 5xx), network failures and timeouts.
+    /// Rate limits are retried only if the wait is short enough.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::GraphqlError { kind, .. } | Self::HttpError { kind, .. } => kind.is_transient(),
+            Self::RateLimited { .. } | Self::Timeout { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// How long to wait before retrying, when GitHub said so
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => retry_after.map(std::time::Duration::from_secs),
+            _ => None,
+        }
+    }
+
+    /// Stable name of the error kind, as printed by `--error-format json`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::GhError(_) => "gh",
+            Self::GhNotInstalled => "gh_not_installed",
+            Self::AuthError(_) => "auth",
+            Self::NotFound { .. } => "not_found",
+            Self::IndexOutOfRange { .. } => "index_out_of_range",
+            Self::InvalidInput(_) => "invalid_input",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::Timeout { .. } => "timeout",
+            Self::HttpError { kind, .. } | Self::GraphqlError { kind, .. } => match kind {
+                GraphqlErrorKind::NotFound => "not_found",
+                GraphqlErrorKind::Forbidden => "forbidden",
+                GraphqlErrorKind::RateLimited => "rate_limited",
+                GraphqlErrorKind::Server => "server_error",
+                GraphqlErrorKind::Network => "network",
+                GraphqlErrorKind::Validation | GraphqlErrorKind::Other => "graphql",
+            },
+            Self::StoreError(_) => "store",
+            Self::TemplateError(_) => "template",
+            Self::ConfigError(_) => "config",
+            Self::GitError(_) => "git",
+            Self::IoError(_) => "io",
+            Self::JsonError(_) => "json",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// Process exit code for the error kind (documented in the README)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::GhError(_) | Self::IoError(_) | Self::JsonError(_) | Self::Other(_) => 1,
+            Self::InvalidInput(_) | Self::ConfigError(_) => 2,
+            Self::GhNotInstalled => 3,
+            Self::AuthError(_) => 4,
+            Self::NotFound { .. } => 5,
+            Self::IndexOutOfRange { .. } => 6,
+            Self::RateLimited { .. } => 12,
+            Self::Timeout { .. } => 14,
+            Self::HttpError { kind, .. } | Self::GraphqlError { kind, .. } => match kind {
+                GraphqlErrorKind::NotFound => 5,
+                GraphqlErrorKind::Forbidden => 11,
+                GraphqlErrorKind::RateLimited => 12,
+                GraphqlErrorKind::Server | GraphqlErrorKind::Network => 13,
+                GraphqlErrorKind::Validation | GraphqlErrorKind::Other => 7,
+            },
+            Self::StoreError(_) => 8,
+            Self::GitError(_) => 9,
+            Self::TemplateError(_) => 10,
+        }
+    }
+
+    /// Structured fields of the error, if it has any
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            Self::NotFound { resource, id, location } => Some(serde_json::json!({
+                "resource": resource,
+                "id": id,
+                "location": location,
+            })),
+            Self::IndexOutOfRange { index, total } => Some(serde_json::json!({
+                "index": index,
+                "total": total,
+            })),
+            Self::RateLimited { retry_after, .. } => Some(serde_json::json!({
+                "retryAfter": retry_after,
+            })),
+            Self::Timeout { command, timeout } => Some(serde_json::json!({
+                "command": command,
+                "timeoutMs": timeout.as_millis() as u64,
+            })),
+            Self::HttpError { status, .. } => Some(serde_json::json!({
+                "status": status,
+            })),
+            Self::GraphqlError { kind, path, .. } => Some(serde_json::json!({
+                "type": kind.name(),
+                "path": path,
+            })),
+            _ => None,
+        }
+    }
+
+    /// The error as `{kind, message, details, graphqlErrors}`
+    pub fn to_json(&self) -> Value {
+        let graphql_errors = match self {
+            Self::GraphqlError { errors, .. } => Value::Array(errors.clone()),
+            _ => Value::Null,
+        };
+        serde_json::json!({
+            "kind": self.kind(),
+            "message": self.to_string(),
+            "details": self.details(),
+            "graphqlErrors": graphql_errors,
+        })
+    }
+}
+
+/// How a failing command reports its error on stderr (`--error-format`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// `Error: <message>`
+    #[default]
+    Text,
+    /// A single `{kind, message, details, graphqlErrors}` JSON object
+    Json,
+}
+
+/// Print `error` on stderr in `format`
+pub fn report(error: &GhReplyError, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => eprintln!("Error: {}", error),
+        ErrorFormat::Json => eprintln!("{}", error.to_json()),
+    }
+}
+
 pub type Result<T> = std::result::Result<T, GhReplyError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kinds_and_exit_codes() {
+        let err = GhReplyError::not_found("Thread", "PRRT_x", Some("owner/repo#12".to_string()));
+        assert_eq!(err.to_string(), "Thread PRRT_x not found on owner/repo#12");
+        assert_eq!((err.kind(), err.exit_code()), ("not_found", 5));
+
+        let err = GhReplyError::IndexOutOfRange { index: 9, total: 3 };
+        assert_eq!(
+            err.to_json(),
+            serde_json::json!({
+                "kind": "index_out_of_range",
+                "message": "Thread index 9 is out of range (1-3)",
+                "details": {"index": 9, "total": 3},
+                "graphqlErrors": null,
+            })
+        );
+
+        let errors = vec![serde_json::json!({"message": "Field 'nope' doesn't exist", "extensions": {"code": "undefinedField"}})];
+        let err = GhReplyError::GraphqlError {
+            kind: GraphqlErrorKind::classify(&errors[0]),
+            message: "Field 'nope' doesn't exist".to_string(),
+            path: Some("query.nope".to_string()),
+            errors: errors.clone(),
+        };
+        assert_eq!((err.kind(), err.exit_code()), ("graphql", 7));
+        assert!(!err.is_transient());
+        assert_eq!(err.to_json()["graphqlErrors"], Value::Array(errors));
+        assert_eq!(err.details().unwrap(), serde_json::json!({"type": "validation", "path": "query.nope"}));
+        assert_eq!(GhReplyError::ConfigError("x".to_string()).exit_code(), 2);
+        assert!(!GhReplyError::GhError("Failed to parse JSON".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_graphql_classification() {
+        let classify = |error: Value| GraphqlErrorKind::classify(&error);
+        assert_eq!(classify(serde_json::json!({"type": "NOT_FOUND", "message": "x"})), GraphqlErrorKind::NotFound);
+        assert_eq!(
+            classify(serde_json::json!({"message": "Could not resolve to a PullRequest with the number of 12."})),
+            GraphqlErrorKind::NotFound
+        );
+        assert_eq!(
+            classify(serde_json::json!({"type": "FORBIDDEN", "message": "Resource not accessible by integration"})),
+            GraphqlErrorKind::Forbidden
+        );
+        assert_eq!(classify(serde_json::json!({"message": "API rate limit exceeded for user"})), GraphqlErrorKind::RateLimited);
+        assert_eq!(
+            classify(serde_json::json!({"message": "Something went wrong while executing your query."})),
+            GraphqlErrorKind::Server
+        );
+        assert_eq!(classify(serde_json::json!({"message": "odd"})), GraphqlErrorKind::Other);
+
+        let not_found = GhReplyError::GraphqlError {
+            kind: GraphqlErrorKind::NotFound,
+            message: "Could not resolve to a node with the global id of 'PRRT_x'".to_string(),
+            path: Some("resolveReviewThread".to_string()),
+            errors: Vec::new(),
+        };
+        assert!(!not_found.is_transient());
+        let err = not_found.with_not_found("Thread", "PRRT_x", || Some("owner/repo#12".to_string()));
+        assert_eq!(err.to_string(), "Thread PRRT_x not found on owner/repo#12");
+
+        let server = GhReplyError::GraphqlError {
+            kind: GraphqlErrorKind::Server,
+            message: "Something went wrong".to_string(),
+            path: None,
+            errors: Vec::new(),
+        };
+        assert!(server.is_transient());
+        assert_eq!(server.to_string(), "GraphQL error: Something went wrong (GitHub had a server error; try again later)");
+        assert!(matches!(server.with_not_found("Thread", "x", || None), GhReplyError::GraphqlError { .. }));
+    }
+}
@@ -0,0 +1,177 @@
+use crate::error::{GhReplyError, Result};
+use crate::types::ReviewThread;
+
+/// A single `key:value` term of a thread filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FilterTerm {
+    /// Author of the first comment (`author:NAME`)
+    Author(String),
+    /// Author of the latest comment (`last-author:NAME`)
+    LastAuthor(String),
+    /// File path prefix (`path:PREFIX`)
+    Path(String),
+    /// Case-insensitive substring of the first comment (`contains:TEXT`)
+    Contains(String),
+    /// Resolution state (`resolved:true|false`)
+    Resolved(bool),
+    /// Outdated state (`outdated:true|false`)
+    Outdated(bool),
+}
+
+/// Thread filter expression such as `last-author:@me path:src/`.
+///
+/// Terms are separated by whitespace and must all match. A term prefixed
+/// with `-` is negated. `@me` stands for the authenticated user.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadFilter {
+    terms: Vec<(bool, FilterTerm)>,
+}
+
+impl ThreadFilter {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+
+        for token in expr.split_whitespace() {
+            let (negated, token) = match token.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, token),
+            };
+
+            let (key, value) = token.split_once(':').ok_or_else(|| {
+                GhReplyError::InvalidInput(format!(
+                    "Invalid filter term '{}'. Expected key:value",
+                    token
+                ))
+            })?;
+
+            let term = match key {
+                "author" => FilterTerm::Author(value.to_string()),
+                "last-author" => FilterTerm::LastAuthor(value.to_string()),
+                "path" => FilterTerm::Path(value.to_string()),
+                "contains" => FilterTerm::Contains(value.to_lowercase()),
+                "resolved" => FilterTerm::Resolved(parse_bool(value)?),
+                "outdated" => FilterTerm::Outdated(parse_bool(value)?),
+                other => {
+                    return Err(GhReplyError::InvalidInput(format!(
+                        "Unknown filter key '{}'. Expected one of: author, last-author, path, contains, resolved, outdated",
+                        other
+                    )))
+                }
+            };
+            terms.push((negated, term));
+        }
+
+        Ok(Self { terms })
+    }
+
+    /// Whether any term refers to `@me` and needs the authenticated user
+    pub fn needs_viewer(&self) -> bool {
+        self.terms.iter().any(|(_, term)| {
+            matches!(term, FilterTerm::Author(v) | FilterTerm::LastAuthor(v) if v == "@me")
+        })
+    }
+
+    /// Check whether a thread matches every term.
+    /// `viewer` is the authenticated user's login, substituted for `@me`.
+    pub fn matches(&self, thread: &ReviewThread, viewer: &str) -> bool {
+        self.terms.iter().all(|(negated, term)| {
+            let matched = match term {
+                FilterTerm::Author(name) => login_matches(thread.author.as_deref(), name, viewer),
+                FilterTerm::LastAuthor(name) => {
+                    login_matches(thread.last_author.as_deref(), name, viewer)
+                }
+                FilterTerm::Path(prefix) => thread.path.starts_with(prefix.as_str()),
+                FilterTerm::Contains(text) => thread.body.to_lowercase().contains(text.as_str()),
+                FilterTerm::Resolved(resolved) => thread.is_resolved == *resolved,
+                FilterTerm::Outdated(outdated) => thread.is_outdated == *outdated,
+            };
+            matched != *negated
+        })
+    }
+}
+
+fn login_matches(login: Option<&str>, name: &str, viewer: &str) -> bool {
+    let expected = if name == "@me" { viewer } else { name };
+    match login {
+        Some(login) => !expected.is_empty() && login.eq_ignore_ascii_case(expected),
+        None => false,
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        other => Err(GhReplyError::InvalidInput(format!(
+            "Invalid boolean '{}' in filter. Expected true or false",
+            other
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thread(author: &str, last_author: &str, path: &str, resolved: bool) -> ReviewThread {
+        ReviewThread {
+            id: "PRRT_1".to_string(),
+            path: path.to_string(),
+            line: Some(1),
+            start_line: None,
+            diff_side: None,
+            body: "Please Rename this".to_string(),
+            is_resolved: resolved,
+            is_outdated: false,
+            original_line: Some(1),
+            original_start_line: None,
+            subject_type: Some("LINE".to_string()),
+            author: Some(author.to_string()),
+            last_author: Some(last_author.to_string()),
+            commit_oid: None,
+            original_commit_oid: None,
+            current_line: None,
+            line_status: None,
+        }
+    }
+
+    #[test]
+    fn test_last_author_me() {
+        let filter = ThreadFilter::parse("last-author:@me").unwrap();
+        assert!(filter.needs_viewer());
+        assert!(filter.matches(&thread("reviewer", "me", "src/a.rs", false), "me"));
+        assert!(!filter.matches(&thread("reviewer", "reviewer", "src/a.rs", false), "me"));
+    }
+
+    #[test]
+    fn test_multiple_terms_and_negation() {
+        let filter = ThreadFilter::parse("path:src/ -author:@me contains:rename").unwrap();
+        assert!(filter.matches(&thread("reviewer", "me", "src/a.rs", false), "me"));
+        assert!(!filter.matches(&thread("me", "me", "src/a.rs", false), "me"));
+        assert!(!filter.matches(&thread("reviewer", "me", "docs/a.md", false), "me"));
+    }
+
+    #[test]
+    fn test_resolved_term() {
+        let filter = ThreadFilter::parse("resolved:false").unwrap();
+        assert!(!filter.needs_viewer());
+        assert!(filter.matches(&thread("a", "b", "x", false), ""));
+        assert!(!filter.matches(&thread("a", "b", "x", true), ""));
+    }
+
+    #[test]
+    fn test_outdated_term() {
+        let filter = ThreadFilter::parse("outdated:true").unwrap();
+        let mut t = thread("a", "b", "x", false);
+        assert!(!filter.matches(&t, ""));
+        t.is_outdated = true;
+        assert!(filter.matches(&t, ""));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(ThreadFilter::parse("author").is_err());
+        assert!(ThreadFilter::parse("severity:high").is_err());
+        assert!(ThreadFilter::parse("resolved:maybe").is_err());
+    }
+}
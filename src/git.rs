@@ -0,0 +1,275 @@
+use crate::error::{GhReplyError, Result};
+use std::path::PathBuf;
+use tokio::process::Command;
+
+/// Run a git command in the current directory and return its stdout
+pub async fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| GhReplyError::GitError(format!("Failed to execute git: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(GhReplyError::GitError(format!(
+            "git command failed: {}\nstderr: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Absolute path of the repository root
+pub async fn repo_root() -> Result<PathBuf> {
+    let root = git(&["rev-parse", "--show-toplevel"]).await?;
+    Ok(PathBuf::from(root.trim()))
+}
+
+/// Ensure a commit exists locally, with a hint to fetch it otherwise
+pub async fn ensure_commit(oid: &str) -> Result<()> {
+    let spec = format!("{}^{{commit}}", oid);
+    git(&["cat-file", "-e", &spec]).await.map_err(|_| {
+        GhReplyError::GitError(format!(
+            "Commit {} is not available locally. Run `git fetch` and try again.",
+            oid
+        ))
+    })?;
+    Ok(())
+}
+
+/// Whether `path` (relative to the repository root) exists at `commit`
+pub async fn exists_at(commit: &str, path: &str) -> bool {
+    git(&["cat-file", "-e", &format!("{}:{}", commit, path)]).await.is_ok()
+}
+
+/// Content of `path` (relative to the repository root) at `commit`
+pub async fn file_at(commit: &str, path: &str) -> Result<String> {
+    git(&["show", &format!("{}:{}", commit, path)]).await
+}
+
+/// Content of `path` (relative to the repository root) in the working tree
+pub async fn worktree_file(path: &str) -> Result<String> {
+    let full_path = repo_root().await?.join(path);
+    Ok(tokio::fs::read_to_string(full_path).await?)
+}
+
+/// Hunks of `git diff -U0 <commit>` for `path`, comparing against the
+/// working tree, or against `to` when given
+pub async fn diff_hunks(commit: &str, to: Option<&str>, path: &str) -> Result<Vec<Hunk>> {
+    let root = repo_root().await?;
+    let root = root.to_string_lossy();
+    let mut args = vec!["-C", &root, "diff", "-U0", "--no-color", "--no-ext-diff", commit];
+    if let Some(to) = to {
+        args.push(to);
+    }
+    args.extend(["--", path]);
+    Ok(parse_hunks(&git(&args).await?))
+}
+
+/// Commits in `from..to` that touched `path`, oldest first
+pub async fn commits_touching(from: &str, to: &str, path: &str) -> Result<Vec<String>> {
+    let root = repo_root().await?;
+    let root = root.to_string_lossy();
+    let range = format!("{}..{}", from, to);
+    let log = git(&["-C", &root, "log", "--reverse", "--format=%H", &range, "--", path]).await?;
+    Ok(log.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect())
+}
+
+/// SHA and full message of each commit in `range`, oldest first
+pub async fn commit_messages(range: &str) -> Result<Vec<(String, String)>> {
+    let log = git(&["log", "--reverse", "--format=%H%x1f%B%x1e", range]).await?;
+    Ok(log
+        .split('\x1e')
+        .filter_map(|entry| entry.trim_start().split_once('\x1f'))
+        .map(|(sha, message)| (sha.to_string(), message.to_string()))
+        .collect())
+}
+
+/// Lines `start..=end` (1-based) of `text`
+pub fn line_range(text: &str, start: u32, end: u32) -> Vec<&str> {
+    if start == 0 || end < start {
+        return Vec::new();
+    }
+    text.lines()
+        .skip(start as usize - 1)
+        .take((end - start + 1) as usize)
+        .collect()
+}
+
+/// A hunk header of a zero-context unified diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: u32,
+    pub old_count: u32,
+    pub new_start: u32,
+    pub new_count: u32,
+}
+
+impl Hunk {
+    /// Whether the hunk's old side lies entirely before `line`
+    fn before(&self, line: u32) -> bool {
+        if self.old_count == 0 {
+            self.old_start < line
+        } else {
+            self.old_start + self.old_count - 1 < line
+        }
+    }
+
+    /// Whether the hunk's old side lies entirely after `line`
+    fn after(&self, line: u32) -> bool {
+        if self.old_count == 0 {
+            self.old_start >= line
+        } else {
+            self.old_start > line
+        }
+    }
+
+    fn delta(&self) -> i64 {
+        self.new_count as i64 - self.old_count as i64
+    }
+}
+
+/// Parse `@@ -a,b +c,d @@` headers out of a unified diff
+pub fn parse_hunks(diff: &str) -> Vec<Hunk> {
+    let re = regex::Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap();
+    diff.lines()
+        .filter_map(|line| re.captures(line))
+        .map(|caps| {
+            let num = |i: usize, default: u32| {
+                caps.get(i)
+                    .and_then(|m| m.as_str().parse().ok())
+                    .unwrap_or(default)
+            };
+            Hunk {
+                old_start: num(1, 0),
+                old_count: num(2, 1),
+                new_start: num(3, 0),
+                new_count: num(4, 1),
+            }
+        })
+        .collect()
+}
+
+/// Where an old line range ended up after a diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MappedRange {
+    /// First line of the range on the new side
+    pub start: u32,
+    /// Last line of the range on the new side (less than `start` if deleted)
+    pub end: u32,
+    /// Some lines in the range were modified
+    pub changed: bool,
+    /// Every line in the range was removed
+    pub deleted: bool,
+    /// A change overlapping the range also touches lines outside it
+    pub extends_outside: bool,
+}
+
+/// Map the old line range `start..=end` through the hunks of a diff
+pub fn map_range(hunks: &[Hunk], start: u32, end: u32) -> MappedRange {
+    let mut shift: i64 = 0;
+    let mut inner_delta: i64 = 0;
+    let mut changed = false;
+    let mut extends_outside = false;
+
+    for hunk in hunks {
+        if hunk.before(start) {
+            shift += hunk.delta();
+        } else if !hunk.after(end) {
+            changed = true;
+            inner_delta += hunk.delta();
+            if hunk.old_count > 0
+                && (hunk.old_start < start || hunk.old_start + hunk.old_count - 1 > end)
+            {
+                extends_outside = true;
+            }
+        }
+    }
+
+    let new_start = (start as i64 + shift).max(1);
+    let new_end = end as i64 + shift + inner_delta;
+    MappedRange {
+        start: new_start as u32,
+        end: new_end.max(0) as u32,
+        changed,
+        deleted: new_end < new_start,
+        extends_outside,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/x.rs b/src/x.rs
+--- a/src/x.rs
++++ b/src/x.rs
+@@ -2,0 +3,2 @@ fn main() {
++    let a = 1;
++    let b = 2;
+@@ -10 +12 @@ fn helper() {
+-    old();
++    new();
+@@ -20,3 +21,0 @@
+-    gone1();
+-    gone2();
+-    gone3();
+";
+
+    #[test]
+    fn test_parse_hunks() {
+        let hunks = parse_hunks(DIFF);
+        assert_eq!(hunks.len(), 3);
+        assert_eq!(hunks[0], Hunk { old_start: 2, old_count: 0, new_start: 3, new_count: 2 });
+        assert_eq!(hunks[1], Hunk { old_start: 10, old_count: 1, new_start: 12, new_count: 1 });
+        assert_eq!(hunks[2], Hunk { old_start: 20, old_count: 3, new_start: 21, new_count: 0 });
+    }
+
+    #[test]
+    fn test_map_unchanged_range_is_shifted() {
+        let hunks = parse_hunks(DIFF);
+        let mapped = map_range(&hunks, 5, 6);
+        assert_eq!((mapped.start, mapped.end), (7, 8));
+        assert!(!mapped.changed);
+        assert!(!mapped.deleted);
+    }
+
+    #[test]
+    fn test_map_changed_line() {
+        let hunks = parse_hunks(DIFF);
+        let mapped = map_range(&hunks, 10, 10);
+        assert_eq!((mapped.start, mapped.end), (12, 12));
+        assert!(mapped.changed);
+        assert!(!mapped.extends_outside);
+    }
+
+    #[test]
+    fn test_map_deleted_range() {
+        let hunks = parse_hunks(DIFF);
+        let mapped = map_range(&hunks, 20, 22);
+        assert!(mapped.changed);
+        assert!(mapped.deleted);
+
+        let partial = map_range(&hunks, 21, 21);
+        assert!(partial.deleted);
+        assert!(partial.extends_outside);
+    }
+
+    #[test]
+    fn test_map_without_hunks() {
+        let mapped = map_range(&[], 3, 4);
+        assert_eq!((mapped.start, mapped.end), (3, 4));
+        assert!(!mapped.changed);
+    }
+
+    #[test]
+    fn test_line_range() {
+        let text = "a\nb\nc\nd\n";
+        assert_eq!(line_range(text, 2, 3), vec!["b", "c"]);
+        assert!(line_range(text, 3, 2).is_empty());
+        assert!(line_range(text, 0, 1).is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use super::policy::{parse_duration, setting};
+use crate::error::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Environment variable setting how long cached responses are reused
+pub const CACHE_TTL_ENV: &str = "GHREPLY_CACHE_TTL";
+
+/// How long cached responses are reused by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60);
+
+/// Short-lived cache of GitHub lookups under `.git/gh-reply-cache/`, one
+/// JSON file per entry. Entries of a pull request live under
+/// `prs/<owner>/<repo>/<number>/` so mutations can drop them together.
+/// Cache failures are never fatal: a broken entry is a miss.
+#[derive(Debug, Clone, Default)]
+pub struct Cache {
+    /// `None` disables the cache entirely
+    dir: Option<PathBuf>,
+    ttl: Duration,
+    /// `--no-cache`: neither read nor write entries, but still invalidate
+    /// them so later cached runs don't see stale data
+    bypass: bool,
+}
+
+impl Cache {
+    pub fn new(dir: PathBuf, ttl: Duration) -> Self {
+        Self {
+            dir: Some(dir),
+            ttl,
+            bypass: false,
+        }
+    }
+
+    /// The cache of the current repository, with the TTL from
+    /// `GHREPLY_CACHE_TTL` or `git config gh-reply.cacheTtl`. Outside a git
+    /// repository caching is disabled.
+    pub async fn open(bypass: bool) -> Result<Self> {
+        let ttl = setting(CACHE_TTL_ENV, "cacheTtl", parse_duration).await?.unwrap_or(DEFAULT_TTL);
+        let Ok(git_dir) = crate::git::git(&["rev-parse", "--absolute-git-dir"]).await else {
+            return Ok(Self::default());
+        };
+
+        Ok(Self {
+            dir: Some(PathBuf::from(git_dir.trim()).join("gh-reply-cache")),
+            ttl,
+            bypass,
+        })
+    }
+
+    /// Key of a per-PR entry such as its thread list
+    pub fn pr_key(owner: &str, name: &str, pr_number: impl std::fmt::Display, entry: &str) -> String {
+        format!("prs/{}/{}/{}/{}", owner, name, pr_number, entry)
+    }
+
+    /// The cached value for `key`, unless missing, unreadable or expired
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        if self.bypass || self.ttl.is_zero() {
+            return None;
+        }
+        let content = fs::read_to_string(self.path(key)?).ok()?;
+        let entry: Value = serde_json::from_str(&content).ok()?;
+        let age = now().checked_sub(Duration::from_secs(entry["storedAt"].as_u64()?))?;
+        if age >= self.ttl {
+            return None;
+        }
+        serde_json::from_value(entry["value"].clone()).ok()
+    }
+
+    pub fn put<T: Serialize>(&self, key: &str, value: &T) {
+        if self.bypass || self.ttl.is_zero() {
+            return;
+        }
+        let Some(path) = self.path(key) else {
+            return;
+        };
+        let entry = serde_json::json!({
+            "storedAt": now().as_secs(),
+            "value": value,
+        });
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).ok();
+        }
+        fs::write(path, entry.to_string()).ok();
+    }
+
+    /// Drop every per-PR entry, after a mutation changed threads or comments
+    pub fn invalidate_prs(&self) {
+        if let Some(dir) = &self.dir {
+            fs::remove_dir_all(dir.join("prs")).ok();
+        }
+    }
+
+    fn path(&self, key: &str) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(format!("{}.json", key)))
+    }
+}
+
+fn now() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_cache(ttl: Duration) -> Cache {
+        let dir = std::env::temp_dir().join(format!("gh-reply-cache-test-{}-{}", std::process::id(), rand::random::<u32>()));
+        Cache::new(dir, ttl)
+    }
+
+    #[test]
+    fn test_put_get_and_invalidate() {
+        let cache = temp_cache(DEFAULT_TTL);
+        let threads = Cache::pr_key("owner", "repo", 12, "threads");
+        cache.put("viewer", &"testuser");
+        cache.put(&threads, &vec!["PRRT_a"]);
+
+        assert_eq!(cache.get::<String>("viewer").as_deref(), Some("testuser"));
+        assert_eq!(cache.get::<Vec<String>>(&threads), Some(vec!["PRRT_a".to_string()]));
+        assert_eq!(cache.get::<u32>("missing"), None);
+
+        cache.invalidate_prs();
+        assert_eq!(cache.get::<Vec<String>>(&threads), None);
+        assert_eq!(cache.get::<String>("viewer").as_deref(), Some("testuser"));
+
+        fs::remove_dir_all(cache.dir.unwrap()).ok();
+    }
+
+    #[test]
+    fn test_expired_and_bypassed_entries_are_misses() {
+        let cache = temp_cache(DEFAULT_TTL);
+        let path = cache.path("viewer").unwrap();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let stale = now().as_secs() - DEFAULT_TTL.as_secs();
+        fs::write(&path, serde_json::json!({"storedAt": stale, "value": "old"}).to_string()).unwrap();
+        assert_eq!(cache.get::<String>("viewer"), None);
+
+        let bypassed = Cache { bypass: true, ..cache.clone() };
+        bypassed.put("viewer", &"new");
+        assert_eq!(bypassed.get::<String>("viewer"), None);
+        assert_eq!(cache.get::<String>("viewer"), None);
+
+        assert_eq!(Cache::default().get::<String>("viewer"), None);
+        fs::remove_dir_all(cache.dir.unwrap()).ok();
+    }
+}
@@ -1,23 +1,73 @@
-use crate::error::{GhReplyError, Result};
-use crate::types::ReviewThread;
+use crate::error::{GhReplyError, GraphqlErrorKind, Result};
+use crate::policy::ThreadAuthor;
+use crate::types::{NewThreadTarget, PostedComment, ReviewThread, ThreadComment};
+use super::cache::Cache;
+use super::policy::{format_duration, RequestKind, RequestPolicy};
+use super::rate_limit::{with_rate_limit, RateLimit, Response, MAX_RATE_LIMIT_WAIT};
+use chrono::Utc;
 use serde_json::Value;
-use std::process::Command;
+use std::process::Output;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::process::Command;
 
 #[derive(Clone)]
 pub struct GhClient {
     repo: Option<String>,
+    /// Shared by clones so concurrent requests see the same budget
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+    policy: RequestPolicy,
+    cache: Cache,
+    verbose: bool,
 }
 
 impl GhClient {
     pub fn new(repo: Option<String>) -> Self {
-        Self { repo }
+        Self {
+            repo,
+            rate_limit: Arc::new(Mutex::new(None)),
+            policy: RequestPolicy::default(),
+            cache: Cache::default(),
+            verbose: false,
+        }
+    }
+
+    /// Reuse repo info, the viewer, PR details and thread lists from `cache`
+    pub fn cache(mut self, cache: Cache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Timeout and retry settings for gh requests
+    pub fn policy(mut self, policy: RequestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Report the rate-limit budget and retries on stderr
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
     }
 
     /// Execute gh CLI command with environment setup
-    fn gh_exec(&self, args: &[&str], input: Option<&str>) -> Result<String> {
-        // Allow overriding gh command for testing
-        let gh_cmd = std::env::var("GH_COMMAND").unwrap_or_else(|_| "gh".to_string());
-        let mut cmd = Command::new(&gh_cmd);
+    async fn gh_exec(&self, args: &[&str], input: Option<&str>) -> Result<String> {
+        let output = self.gh_run(args, input).await?;
+
+        if !output.status.success() {
+            return Err(command_error(args, &output.stdout, &output.stderr));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Run gh and collect its output, whatever the exit status. The process
+    /// is killed if it runs longer than the policy's timeout.
+    async fn gh_run(&self, args: &[&str], input: Option<&str>) -> Result<Output> {
+        use std::process::Stdio;
+        use tokio::io::AsyncWriteExt;
+
+        let mut cmd = gh_command();
         cmd.args(args);
 
         // Set environment variables for non-interactive, deterministic behavior
@@ -27,183 +77,243 @@ impl GhClient {
         cmd.env("CLICOLOR", "0");
         cmd.env("CI", "true");
 
-        if let Some(input_str) = input {
-            use std::io::Write;
-            use std::process::Stdio;
+        cmd.stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // Dropping the child on timeout kills it
+            .kill_on_drop(true);
 
-            cmd.stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped());
+        let mut child = cmd.spawn().map_err(|e| spawn_error(e, "spawn"))?;
 
-            let mut child = cmd
-                .spawn()
-                .map_err(|e| GhReplyError::GhError(format!("Failed to spawn gh: {}", e)))?;
-
-            if let Some(mut stdin) = child.stdin.take() {
+        let run = async move {
+            if let (Some(mut stdin), Some(input_str)) = (child.stdin.take(), input) {
                 stdin
                     .write_all(input_str.as_bytes())
+                    .await
                     .map_err(|e| GhReplyError::GhError(format!("Failed to write to stdin: {}", e)))?;
             }
 
-            let output = child
+            child
                 .wait_with_output()
-                .map_err(|e| GhReplyError::GhError(format!("Failed to wait for gh: {}", e)))?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return Err(GhReplyError::GhError(format!(
-                    "gh command failed: {}\nstderr: {}",
-                    args.join(" "),
-                    stderr
-                )));
-            }
-
-            return Ok(String::from_utf8_lossy(&output.stdout).to_string());
-        }
-
-        let output = cmd
-            .output()
-            .map_err(|e| GhReplyError::GhError(format!("Failed to execute gh: {}", e)))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(GhReplyError::GhError(format!(
-                "gh command failed: {}\nstderr: {}",
-                args.join(" "),
-                stderr
-            )));
+                .await
+                .map_err(|e| GhReplyError::GhError(format!("Failed to wait for gh: {}", e)))
+        };
+
+        match self.policy.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run).await.map_err(|_| GhReplyError::Timeout {
+                command: args.iter().take(2).copied().collect::<Vec<_>>().join(" "),
+                timeout,
+            })?,
+            None => run.await,
         }
-
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
     /// Ensure gh CLI is available and authenticated
-    pub fn ensure_gh_available() -> Result<()> {
-        let gh_cmd = std::env::var("GH_COMMAND").unwrap_or_else(|_| "gh".to_string());
-
-        let output = Command::new(&gh_cmd)
-            .args(&["--version"])
+    pub async fn ensure_gh_available() -> Result<()> {
+        let output = gh_command()
+            .args(["--version"])
             .output()
-            .map_err(|_| {
-                GhReplyError::GhError(
-                    "`gh` CLI is not installed or not in PATH. Install GitHub CLI: https://cli.github.com/".to_string(),
-                )
-            })?;
+            .await
+            .map_err(|_| GhReplyError::GhNotInstalled)?;
 
         if !output.status.success() {
-            return Err(GhReplyError::GhError(
-                "`gh` CLI is not installed or not in PATH.".to_string(),
-            ));
+            return Err(GhReplyError::GhNotInstalled);
         }
 
-        let output = Command::new(&gh_cmd)
-            .args(&["auth", "status"])
+        let output = gh_command()
+            .args(["auth", "status"])
             .output()
-            .map_err(|_| {
-                GhReplyError::GhError(
-                    "`gh` is not authenticated. Run `gh auth login` to authenticate.".to_string(),
-                )
-            })?;
+            .await
+            .map_err(|e| GhReplyError::AuthError(e.to_string()))?;
 
         if !output.status.success() {
-            return Err(GhReplyError::GhError(
-                "`gh` is not authenticated. Run `gh auth login` to authenticate.".to_string(),
-            ));
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("not logged in");
+            return Err(GhReplyError::AuthError(reason.trim().to_string()));
         }
 
         Ok(())
     }
 
     /// Execute gh command and parse JSON output
-    fn gh_json(&self, args: &[&str]) -> Result<Value> {
-        let output = self.gh_exec(args, None)?;
+    async fn gh_json(&self, args: &[&str]) -> Result<Value> {
+        let output = self.gh_exec(args, None).await?;
         serde_json::from_str(&output)
             .map_err(|e| GhReplyError::GhError(format!("Failed to parse JSON: {}", e)))
     }
 
-    /// Execute GraphQL query with retry
+    /// Execute GraphQL query, retrying failures as the request policy
+    /// allows: queries on any transient failure, mutations only when
+    /// rejected by a rate limit. Requests are spaced out as the rate-limit
+    /// budget runs low.
     pub async fn gh_graphql(&self, query: &str, variables: Option<Value>) -> Result<Value> {
-        let retries = 3;
-        let base_delay_ms = 500;
+        let kind = RequestKind::of(query);
+        let result = self.gh_graphql_with_retries(kind, query, variables).await;
+
+        // Even a failed mutation may have been applied
+        if kind == RequestKind::Mutation {
+            self.cache.invalidate_prs();
+        }
+        result
+    }
+
+    async fn gh_graphql_with_retries(&self, kind: RequestKind, query: &str, variables: Option<Value>) -> Result<Value> {
         let mut attempt = 0;
-        let mut last_err = None;
 
-        while attempt < retries {
-            let result = self.gh_graphql_once(query, variables.as_ref());
-            match result {
+        loop {
+            self.throttle().await?;
+            let err = match self.gh_graphql_once(query, variables.as_ref()).await {
                 Ok(value) => return Ok(value),
-                Err(e) => {
-                    last_err = Some(e);
-                    attempt += 1;
-                    if attempt >= retries {
-                        break;
-                    }
-
-                    // Exponential backoff with jitter
-                    let exp = 2_u32.pow(attempt);
-                    let max_delay = base_delay_ms * exp;
-                    let delay = rand::random::<u64>() % max_delay as u64;
-                    tokio::time::sleep(tokio::time::Duration::from_millis(delay)).await;
-                }
+                Err(err) => err,
+            };
+
+            attempt += 1;
+            if attempt > self.policy.retries || !self.policy.should_retry(kind, &err) {
+                return Err(err);
             }
+
+            // Wait as long as GitHub asked, or back off exponentially with jitter
+            let delay = match err.retry_after() {
+                Some(wait) if wait > MAX_RATE_LIMIT_WAIT => return Err(err),
+                Some(wait) => wait,
+                None => self.policy.backoff(attempt),
+            };
+            if self.verbose {
+                eprintln!("Retrying in {} ({}/{}): {}", format_duration(&delay), attempt, self.policy.retries, err);
+            }
+            tokio::time::sleep(delay).await;
         }
+    }
 
-        Err(last_err.unwrap_or_else(|| GhReplyError::GhError("Retry failed".to_string())))
+    /// Pause before a request while the remaining budget is low, so bulk
+    /// operations spread their requests until the reset instead of failing
+    /// halfway through
+    async fn throttle(&self) -> Result<()> {
+        let Some(rate) = self.rate_limit() else {
+            return Ok(());
+        };
+        let Some(delay) = rate.throttle_delay(Utc::now()) else {
+            return Ok(());
+        };
+        if delay > MAX_RATE_LIMIT_WAIT {
+            return Err(GhReplyError::RateLimited {
+                message: format!("GitHub API rate limit exhausted; {}", rate.describe()),
+                retry_after: Some(delay.as_secs()),
+            });
+        }
+        if self.verbose {
+            eprintln!("Rate limit low ({}); waiting {:.1}s", rate.describe(), delay.as_secs_f64());
+        }
+        tokio::time::sleep(delay).await;
+        Ok(())
     }
 
-    fn gh_graphql_once(&self, query: &str, variables: Option<&Value>) -> Result<Value> {
-        let q = query.split_whitespace().collect::<Vec<_>>().join(" ");
+    /// Rate-limit budget reported by the last GraphQL response
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.lock().ok().and_then(|rate| rate.clone())
+    }
 
-        if let Some(vars) = variables {
+    fn record_rate_limit(&self, rate: RateLimit) {
+        if self.verbose {
+            eprintln!("Rate limit: {}", rate.describe());
+        }
+        if let Ok(mut current) = self.rate_limit.lock() {
+            *current = Some(rate);
+        }
+    }
+
+    async fn gh_graphql_once(&self, query: &str, variables: Option<&Value>) -> Result<Value> {
+        let q = with_rate_limit(&query.split_whitespace().collect::<Vec<_>>().join(" "));
+
+        let body_str;
+        let query_arg;
+        let mut args = vec!["api", "graphql"];
+        let input = if let Some(vars) = variables {
             let body = serde_json::json!({
                 "query": q,
                 "variables": vars
             });
-            let body_str = serde_json::to_string(&body)?;
-            let args = vec!["api", "graphql", "--input", "-"];
-            let output = self.gh_exec(&args, Some(&body_str))?;
-            let parsed: Value = serde_json::from_str(&output)?;
-
-            if let Some(errors) = parsed.get("errors") {
-                return Err(GhReplyError::GhError(format!(
-                    "GraphQL errors: {}",
-                    serde_json::to_string(errors).unwrap_or_default()
-                )));
-            }
-
-            Ok(parsed)
+            body_str = serde_json::to_string(&body)?;
+            args.extend(["--input", "-"]);
+            Some(body_str.as_str())
         } else {
-            let query_arg = format!("query={}", q);
-            let args = vec!["api", "graphql", "-f", &query_arg];
-            let output = self.gh_exec(&args, None)?;
-            let parsed: Value = serde_json::from_str(&output)?;
-
-            if let Some(errors) = parsed.get("errors") {
-                return Err(GhReplyError::GhError(format!(
-                    "GraphQL errors: {}",
-                    serde_json::to_string(errors).unwrap_or_default()
-                )));
+            query_arg = format!("query={}", q);
+            args.extend(["-f", &query_arg]);
+            None
+        };
+        // --include adds the status line and rate-limit headers to the output
+        args.push("--include");
+
+        let output = self.gh_run(&args, input).await?;
+        let now = Utc::now();
+        let response = Response::parse(&String::from_utf8_lossy(&output.stdout));
+        let header_rate = RateLimit::from_headers(&response.headers);
+
+        if response.is_rate_limited(now) {
+            let message = serde_json::from_str::<Value>(&response.body)
+                .ok()
+                .and_then(|body| body["message"].as_str().map(str::to_string))
+                .unwrap_or_else(|| "GitHub API rate limit exceeded".to_string());
+            if let Some(rate) = header_rate {
+                self.record_rate_limit(rate);
             }
+            return Err(GhReplyError::RateLimited {
+                message,
+                retry_after: response.retry_after(now).map(|wait| wait.as_secs()),
+            });
+        }
 
-            Ok(parsed)
+        let parsed: Option<Value> = serde_json::from_str(&response.body).ok();
+        if let Some(rate) = parsed.as_ref().and_then(RateLimit::from_graphql).or(header_rate.clone()) {
+            self.record_rate_limit(rate);
         }
+
+        if !output.status.success() {
+            let err = command_error(&args, response.body.as_bytes(), &output.stderr);
+            return Err(match err {
+                // Primary rate limit reported in the GraphQL errors
+                GhReplyError::GraphqlError {
+                    kind: GraphqlErrorKind::RateLimited,
+                    message,
+                    ..
+                } => GhReplyError::RateLimited {
+                    message,
+                    retry_after: header_rate.map(|rate| rate.until_reset(now).as_secs()),
+                },
+                err => err,
+            });
+        }
+
+        let parsed = match parsed {
+            Some(parsed) => parsed,
+            None => serde_json::from_str(&response.body)?,
+        };
+        if let Some(errors) = parsed.get("errors") {
+            return Err(graphql_error(errors));
+        }
+
+        Ok(parsed)
     }
 
     /// Get repository information (owner/name)
-    pub fn get_repo_info(&self) -> Result<(String, String)> {
+    pub async fn get_repo_info(&self) -> Result<(String, String)> {
         if let Some(repo_override) = &self.repo {
             let parts: Vec<&str> = repo_override.split('/').collect();
             if parts.len() != 2 {
-                return Err(GhReplyError::GhError(
+                return Err(GhReplyError::InvalidInput(
                     "Invalid repo override. Expected owner/name".to_string(),
                 ));
             }
             return Ok((parts[0].to_string(), parts[1].to_string()));
         }
 
-        let result = self.gh_json(&["repo", "view", "--json", "owner,name"]);
+        if let Some(repo) = self.cache.get("repo") {
+            return Ok(repo);
+        }
+
+        let result = self.gh_json(&["repo", "view", "--json", "owner,name"]).await;
 
-        match result {
+        let repo = match result {
             Ok(data) => {
                 let owner = data["owner"]["login"]
                     .as_str()
@@ -212,20 +322,47 @@ impl GhClient {
                 let name = data["name"]
                     .as_str()
                     .ok_or_else(|| GhReplyError::GhError("Failed to get repo name".to_string()))?;
-                Ok((owner.to_string(), name.to_string()))
+                (owner.to_string(), name.to_string())
             }
             Err(_) => {
                 // Fallback: try to infer from local git remotes
-                self.get_repo_from_git()
+                self.get_repo_from_git().await?
             }
+        };
+        self.cache.put("repo", &repo);
+        Ok(repo)
+    }
+
+    /// `owner/repo#N` for error messages, or `PR #N` when the repository
+    /// cannot be determined
+    pub async fn pr_location(&self, pr_number: u32) -> String {
+        match self.get_repo_info().await {
+            Ok((owner, name)) => format!("{}/{}#{}", owner, name, pr_number),
+            Err(_) => format!("PR #{}", pr_number),
         }
     }
 
-    fn get_repo_from_git(&self) -> Result<(String, String)> {
+    /// `owner/repo` for error messages
+    async fn repo_location(&self) -> Option<String> {
+        self.get_repo_info().await.ok().map(|(owner, name)| format!("{}/{}", owner, name))
+    }
+
+    /// Await `request`, turning a GraphQL NOT_FOUND into a `not found` error
+    /// naming `resource` and the repository. The repository is only looked
+    /// up when the request failed that way.
+    async fn not_found_as<T>(&self, resource: &str, id: &str, request: impl Future<Output = Result<T>>) -> Result<T> {
+        match request.await {
+            Err(e) if e.is_graphql_not_found() => Err(GhReplyError::not_found(resource, id, self.repo_location().await)),
+            result => result,
+        }
+    }
+
+    async fn get_repo_from_git(&self) -> Result<(String, String)> {
         // Try to get remote URL from git
         let output = Command::new("git")
-            .args(&["remote", "get-url", "origin"])
+            .args(["remote", "get-url", "origin"])
             .output()
+            .await
             .map_err(|_| GhReplyError::GhError("Failed to get git remote".to_string()))?;
 
         if !output.status.success() {
@@ -251,13 +388,20 @@ impl GhClient {
     }
 
     /// Get authenticated user login
-    pub fn get_authenticated_user(&self) -> Result<String> {
-        match self.gh_json(&["api", "user"]) {
+    pub async fn get_authenticated_user(&self) -> Result<String> {
+        if let Some(login) = self.cache.get("viewer") {
+            return Ok(login);
+        }
+
+        match self.gh_json(&["api", "user"]).await {
             Ok(data) => {
                 let login = data["login"]
                     .as_str()
                     .unwrap_or("")
                     .to_string();
+                if !login.is_empty() {
+                    self.cache.put("viewer", &login);
+                }
                 Ok(login)
             }
             Err(_) => Ok(String::new()),
@@ -265,7 +409,7 @@ impl GhClient {
     }
 
     /// Get PR details
-    pub fn get_pr_details(&self, pr_number: &str) -> Result<Value> {
+    pub async fn get_pr_details(&self, pr_number: &str) -> Result<Value> {
         let mut args = vec![
             "pr",
             "view",
@@ -281,12 +425,29 @@ impl GhClient {
             args.push(&repo_arg);
         }
 
-        self.gh_json(&args)
+        let key = match self.get_repo_info().await {
+            Ok((owner, name)) => Some(Cache::pr_key(&owner, &name, pr_number, "details")),
+            Err(_) => None,
+        };
+        if let Some(details) = key.as_deref().and_then(|key| self.cache.get(key)) {
+            return Ok(details);
+        }
+
+        let details = self.gh_json(&args).await?;
+        if let Some(key) = &key {
+            self.cache.put(key, &details);
+        }
+        Ok(details)
     }
 
     /// Get PR review threads with pagination
     pub async fn get_review_threads(&self, pr_number: u32) -> Result<Vec<ReviewThread>> {
-        let (owner, name) = self.get_repo_info()?;
+        let (owner, name) = self.get_repo_info().await?;
+        let key = Cache::pr_key(&owner, &name, pr_number, "threads");
+        if let Some(threads) = self.cache.get(&key) {
+            return Ok(threads);
+        }
+
         let mut result = Vec::new();
         let mut cursor: Option<String> = None;
         let mut has_next_page = true;
@@ -308,13 +469,25 @@ impl GhClient {
                                 nodes {{
                                     id
                                     isResolved
+                                    isOutdated
                                     path
                                     line
+                                    startLine
+                                    originalLine
+                                    originalStartLine
                                     diffSide
+                                    subjectType
                                     comments(first: 1) {{
                                         nodes {{
                                             body
                                             author {{ login }}
+                                            commit {{ oid }}
+                                            originalCommit {{ oid }}
+                                        }}
+                                    }}
+                                    lastComment: comments(last: 1) {{
+                                        nodes {{
+                                            author {{ login }}
                                         }}
                                     }}
                                 }}
@@ -325,7 +498,9 @@ impl GhClient {
                 owner, name, pr_number, after_clause
             );
 
-            let response = self.gh_graphql(&query, None).await?;
+            let response = self.gh_graphql(&query, None).await.map_err(|e| {
+                e.with_not_found("Pull request", &format!("#{}", pr_number), || Some(format!("{}/{}", owner, name)))
+            })?;
 
             let review_threads = &response["data"]["repository"]["pullRequest"]["reviewThreads"];
 
@@ -343,53 +518,354 @@ impl GhClient {
                 let id = thread["id"].as_str().unwrap_or("").to_string();
                 let path = thread["path"].as_str().unwrap_or("").to_string();
                 let line = thread["line"].as_u64().map(|l| l as u32);
+                let start_line = thread["startLine"].as_u64().map(|l| l as u32);
                 let diff_side = thread["diffSide"].as_str().map(|s| s.to_string());
                 let is_resolved = thread["isResolved"].as_bool().unwrap_or(false);
+                let is_outdated = thread["isOutdated"].as_bool().unwrap_or(false);
+                let original_line = thread["originalLine"].as_u64().map(|l| l as u32);
+                let original_start_line = thread["originalStartLine"].as_u64().map(|l| l as u32);
+                let subject_type = thread["subjectType"].as_str().map(|s| s.to_string());
 
                 let body = thread.get("comments")
                     .and_then(|c| c.get("nodes"))
                     .and_then(|n| n.as_array())
-                    .and_then(|nodes| nodes.get(0))
+                    .and_then(|nodes| nodes.first())
                     .and_then(|comment| comment.get("body"))
                     .and_then(|b| b.as_str())
                     .unwrap_or("")
                     .to_string();
+                let author = thread["comments"]["nodes"][0]["author"]["login"]
+                    .as_str()
+                    .map(|s| s.to_string());
+                let last_author = thread["lastComment"]["nodes"][0]["author"]["login"]
+                    .as_str()
+                    .map(|s| s.to_string());
+                let commit_oid = thread["comments"]["nodes"][0]["commit"]["oid"]
+                    .as_str()
+                    .map(|s| s.to_string());
+                let original_commit_oid = thread["comments"]["nodes"][0]["originalCommit"]["oid"]
+                    .as_str()
+                    .map(|s| s.to_string());
 
                 result.push(ReviewThread {
                     id,
                     path,
                     line,
+                    start_line,
                     diff_side,
                     body,
                     is_resolved,
+                    is_outdated,
+                    original_line,
+                    original_start_line,
+                    subject_type,
+                    author,
+                    last_author,
+                    commit_oid,
+                    original_commit_oid,
+                    current_line: None,
+                    line_status: None,
                 });
             }
         }
 
+        self.cache.put(&key, &result);
         Ok(result)
     }
 
-    /// Post a reply to a review thread
+    /// Post a reply to a review thread and return the created comment
     pub async fn post_reply(
         &self,
         pr_number: u32,
         thread_id: &str,
         body: &str,
-    ) -> Result<()> {
+    ) -> Result<PostedComment> {
+        let comment = match self.add_thread_reply(thread_id, body, None).await {
+            Err(e) if e.is_graphql_not_found() => {
+                return Err(GhReplyError::not_found("Thread", thread_id, Some(self.pr_location(pr_number).await)))
+            }
+            result => result?,
+        };
+        eprintln!("Reply posted to thread {}", thread_id);
+        Ok(comment)
+    }
+
+    /// Attach a reply to a pending review instead of posting it immediately
+    pub async fn post_review_reply(
+        &self,
+        review_id: &str,
+        thread_id: &str,
+        body: &str,
+    ) -> Result<PostedComment> {
+        let comment = self
+            .not_found_as("Thread", thread_id, self.add_thread_reply(thread_id, body, Some(review_id)))
+            .await?;
+        eprintln!("Reply to thread {} added to pending review", thread_id);
+        Ok(comment)
+    }
+
+    async fn add_thread_reply(
+        &self,
+        thread_id: &str,
+        body: &str,
+        review_id: Option<&str>,
+    ) -> Result<PostedComment> {
         // Try direct reply via GraphQL mutation
-        let mutation = r#"mutation ($threadId: ID!, $body: String!) {
-            addPullRequestReviewThreadReply(input: {pullRequestReviewThreadId: $threadId, body: $body}) {
-                comment { id }
+        let mutation = r#"mutation ($threadId: ID!, $body: String!, $reviewId: ID) {
+            addPullRequestReviewThreadReply(input: {pullRequestReviewThreadId: $threadId, body: $body, pullRequestReviewId: $reviewId}) {
+                comment { id databaseId url createdAt }
             }
         }"#;
 
         let variables = serde_json::json!({
             "threadId": thread_id,
+            "body": body,
+            "reviewId": review_id
+        });
+
+        let response = self.gh_graphql(mutation, Some(variables)).await?;
+        let comment = &response["data"]["addPullRequestReviewThreadReply"]["comment"];
+        let id = comment["id"].as_str().ok_or_else(|| {
+            GhReplyError::GhError(format!("Reply to thread {} returned no comment", thread_id))
+        })?;
+
+        Ok(PostedComment {
+            thread_id: thread_id.to_string(),
+            id: id.to_string(),
+            database_id: comment["databaseId"].as_u64(),
+            url: comment["url"].as_str().unwrap_or_default().to_string(),
+            created_at: comment["createdAt"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+
+    /// Start a new review thread on a diff location within a pending review
+    pub async fn add_review_thread(
+        &self,
+        review_id: &str,
+        target: &NewThreadTarget,
+        body: &str,
+    ) -> Result<PostedComment> {
+        let mutation = r#"mutation($reviewId: ID!, $path: String!, $body: String!, $line: Int!, $side: DiffSide, $startLine: Int, $startSide: DiffSide) {
+            addPullRequestReviewThread(input: {pullRequestReviewId: $reviewId, path: $path, body: $body, line: $line, side: $side, startLine: $startLine, startSide: $startSide}) {
+                thread { id comments(first: 1) { nodes { id databaseId url createdAt } } }
+            }
+        }"#;
+        let side = target.side.as_deref().unwrap_or("RIGHT");
+        let variables = serde_json::json!({
+            "reviewId": review_id,
+            "path": target.path,
+            "body": body,
+            "line": target.line,
+            "side": side,
+            "startLine": target.start_line,
+            "startSide": target.start_line.map(|_| side)
+        });
+
+        let response = self.gh_graphql(mutation, Some(variables)).await?;
+        let thread = &response["data"]["addPullRequestReviewThread"]["thread"];
+        let thread_id = thread["id"].as_str().ok_or_else(|| {
+            GhReplyError::GhError(format!(
+                "Failed to create thread on {}:{} (is the line part of the diff?)",
+                target.path, target.line
+            ))
+        })?;
+        let comment = &thread["comments"]["nodes"][0];
+
+        eprintln!("Thread {} added on {}:{}", thread_id, target.path, target.line);
+        Ok(PostedComment {
+            thread_id: thread_id.to_string(),
+            id: comment["id"].as_str().unwrap_or_default().to_string(),
+            database_id: comment["databaseId"].as_u64(),
+            url: comment["url"].as_str().unwrap_or_default().to_string(),
+            created_at: comment["createdAt"].as_str().unwrap_or_default().to_string(),
+        })
+    }
+
+    /// Get the GraphQL node ID of a pull request
+    pub async fn get_pr_node_id(&self, pr_number: u32) -> Result<String> {
+        let (owner, name) = self.get_repo_info().await?;
+        let query = r#"query($owner: String!, $name: String!, $number: Int!) {
+            repository(owner: $owner, name: $name) {
+                pullRequest(number: $number) { id }
+            }
+        }"#;
+        let variables = serde_json::json!({
+            "owner": owner,
+            "name": name,
+            "number": pr_number
+        });
+
+        let response = self.gh_graphql(query, Some(variables)).await.map_err(|e| {
+            e.with_not_found("Pull request", &format!("#{}", pr_number), || Some(format!("{}/{}", owner, name)))
+        })?;
+        response["data"]["repository"]["pullRequest"]["id"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| GhReplyError::not_found("Pull request", format!("#{}", pr_number), Some(format!("{}/{}", owner, name))))
+    }
+
+    /// Start a pending review on a pull request and return its ID
+    pub async fn start_review(&self, pr_node_id: &str) -> Result<String> {
+        let mutation = r#"mutation($prId: ID!) {
+            addPullRequestReview(input: {pullRequestId: $prId}) {
+                pullRequestReview { id }
+            }
+        }"#;
+        let variables = serde_json::json!({ "prId": pr_node_id });
+
+        let response = self.gh_graphql(mutation, Some(variables)).await?;
+        response["data"]["addPullRequestReview"]["pullRequestReview"]["id"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| GhReplyError::GhError("Failed to start a pending review".to_string()))
+    }
+
+    /// Submit a pending review with an event (COMMENT, APPROVE, REQUEST_CHANGES)
+    pub async fn submit_review(&self, review_id: &str, event: &str, body: Option<&str>) -> Result<Value> {
+        let mutation = r#"mutation($reviewId: ID!, $event: PullRequestReviewEvent!, $body: String) {
+            submitPullRequestReview(input: {pullRequestReviewId: $reviewId, event: $event, body: $body}) {
+                pullRequestReview { id url state }
+            }
+        }"#;
+        let variables = serde_json::json!({
+            "reviewId": review_id,
+            "event": event,
             "body": body
         });
 
+        let response = self.gh_graphql(mutation, Some(variables)).await?;
+        eprintln!("Submitted review {}", review_id);
+        Ok(response["data"]["submitPullRequestReview"]["pullRequestReview"].clone())
+    }
+
+    /// Delete a pending review along with its comments
+    pub async fn delete_review(&self, review_id: &str) -> Result<()> {
+        let mutation = r#"mutation($reviewId: ID!) {
+            deletePullRequestReview(input: {pullRequestReviewId: $reviewId}) {
+                pullRequestReview { id }
+            }
+        }"#;
+        let variables = serde_json::json!({ "reviewId": review_id });
+
         self.gh_graphql(mutation, Some(variables)).await?;
-        eprintln!("Reply posted to thread {}", thread_id);
+        eprintln!("Deleted pending review {}", review_id);
+        Ok(())
+    }
+
+    /// Get the diff hunk the first comment of a review thread was made on
+    pub async fn get_thread_diff_hunk(&self, thread_id: &str) -> Result<Option<String>> {
+        let query = r#"query($id: ID!) {
+            node(id: $id) {
+                ... on PullRequestReviewThread {
+                    comments(first: 1) { nodes { diffHunk } }
+                }
+            }
+        }"#;
+        let variables = serde_json::json!({ "id": thread_id });
+
+        let response = self.not_found_as("Thread", thread_id, self.gh_graphql(query, Some(variables))).await?;
+        Ok(response["data"]["node"]["comments"]["nodes"][0]["diffHunk"]
+            .as_str()
+            .map(|s| s.to_string()))
+    }
+
+    /// Get every comment of a review thread, oldest first
+    pub async fn get_thread_comments(&self, thread_id: &str) -> Result<Vec<ThreadComment>> {
+        let query = r#"query($id: ID!) {
+            node(id: $id) {
+                ... on PullRequestReviewThread {
+                    comments(first: 100) { nodes { author { login } body createdAt } }
+                }
+            }
+        }"#;
+        let variables = serde_json::json!({ "id": thread_id });
+
+        let response = self.not_found_as("Thread", thread_id, self.gh_graphql(query, Some(variables))).await?;
+        let comments = response["data"]["node"]["comments"]["nodes"]
+            .as_array()
+            .map(|nodes| {
+                nodes
+                    .iter()
+                    .map(|c| ThreadComment {
+                        author: c["author"]["login"].as_str().map(|s| s.to_string()),
+                        body: c["body"].as_str().unwrap_or_default().to_string(),
+                        created_at: c["createdAt"].as_str().unwrap_or_default().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(comments)
+    }
+
+    /// Get the body of a review comment
+    pub async fn get_comment_body(&self, comment_id: &str) -> Result<String> {
+        let query = r#"query($id: ID!) {
+            node(id: $id) {
+                ... on PullRequestReviewComment { body }
+            }
+        }"#;
+        let variables = serde_json::json!({ "id": comment_id });
+
+        let response = self.not_found_as("Comment", comment_id, self.gh_graphql(query, Some(variables))).await?;
+        response["data"]["node"]["body"]
+            .as_str()
+            .map(|s| s.to_string())
+            .ok_or_else(|| GhReplyError::not_found("Comment", comment_id, None))
+    }
+
+    /// Find the latest comment by `login` in a review thread
+    pub async fn get_last_comment_by(&self, thread_id: &str, login: &str) -> Result<Option<String>> {
+        let query = r#"query($id: ID!) {
+            node(id: $id) {
+                ... on PullRequestReviewThread {
+                    comments(last: 100) { nodes { id author { login } } }
+                }
+            }
+        }"#;
+        let variables = serde_json::json!({ "id": thread_id });
+
+        let response = self.not_found_as("Thread", thread_id, self.gh_graphql(query, Some(variables))).await?;
+        let comment_id = response["data"]["node"]["comments"]["nodes"]
+            .as_array()
+            .and_then(|nodes| {
+                nodes
+                    .iter()
+                    .rev()
+                    .find(|c| c["author"]["login"].as_str() == Some(login))
+            })
+            .and_then(|c| c["id"].as_str())
+            .map(|s| s.to_string());
+        Ok(comment_id)
+    }
+
+    /// Update the body of a review comment
+    pub async fn update_comment(&self, comment_id: &str, body: &str) -> Result<Value> {
+        let mutation = r#"mutation($commentId: ID!, $body: String!) {
+            updatePullRequestReviewComment(input: {pullRequestReviewCommentId: $commentId, body: $body}) {
+                pullRequestReviewComment { id url }
+            }
+        }"#;
+        let variables = serde_json::json!({
+            "commentId": comment_id,
+            "body": body
+        });
+
+        let response = self.not_found_as("Comment", comment_id, self.gh_graphql(mutation, Some(variables))).await?;
+        eprintln!("Updated comment {}", comment_id);
+        Ok(response["data"]["updatePullRequestReviewComment"]["pullRequestReviewComment"].clone())
+    }
+
+    /// Delete a review comment
+    pub async fn delete_comment(&self, comment_id: &str) -> Result<()> {
+        let mutation = r#"mutation($id: ID!) {
+            deletePullRequestReviewComment(input: {id: $id}) {
+                pullRequestReview { id }
+            }
+        }"#;
+        let variables = serde_json::json!({ "id": comment_id });
+
+        self.not_found_as("Comment", comment_id, self.gh_graphql(mutation, Some(variables))).await?;
+        eprintln!("Deleted comment {}", comment_id);
         Ok(())
     }
 
@@ -404,13 +880,52 @@ impl GhClient {
             "threadId": thread_id
         });
 
-        self.gh_graphql(mutation, Some(variables)).await?;
+        self.not_found_as("Thread", thread_id, self.gh_graphql(mutation, Some(variables))).await?;
         eprintln!("Resolved thread {}", thread_id);
         Ok(())
     }
 
+    /// Get the author of the first comment in a review thread
+    pub async fn get_thread_author(&self, thread_id: &str) -> Result<Option<ThreadAuthor>> {
+        let query = r#"query($id: ID!) {
+            node(id: $id) {
+                ... on PullRequestReviewThread {
+                    comments(first: 1) { nodes { author { __typename login } } }
+                }
+            }
+        }"#;
+        let variables = serde_json::json!({ "id": thread_id });
+
+        let response = self.not_found_as("Thread", thread_id, self.gh_graphql(query, Some(variables))).await?;
+        let author = &response["data"]["node"]["comments"]["nodes"][0]["author"];
+
+        Ok(author["login"].as_str().map(|login| ThreadAuthor {
+            login: login.to_string(),
+            is_bot: author["__typename"].as_str() == Some("Bot") || login.ends_with("[bot]"),
+        }))
+    }
+
+    /// Unresolve a previously resolved review thread
+    pub async fn unresolve_thread(&self, thread_id: &str) -> Result<()> {
+        let mutation = r#"mutation($threadId: ID!) {
+            unresolveReviewThread(input: {threadId: $threadId}) {
+                thread { id }
+            }
+        }"#;
+        let variables = serde_json::json!({
+            "threadId": thread_id
+        });
+
+        self.not_found_as("Thread", thread_id, self.gh_graphql(mutation, Some(variables))).await?;
+        eprintln!("Unresolved thread {}", thread_id);
+        Ok(())
+    }
+
     /// List PRs with state filter
     pub async fn list_prs(&self, state: &str) -> Result<Value> {
+        // gh pr list spends the same GraphQL budget
+        self.throttle().await?;
+
         let mut args = vec![
             "pr",
             "list",
@@ -427,11 +942,109 @@ impl GhClient {
             args.push(&repo_arg);
         }
 
-        self.gh_json(&args)
+        self.gh_json(&args).await
     }
 
 }
 
+/// The gh executable, overridable with `GH_COMMAND` for testing
+fn gh_command() -> Command {
+    Command::new(std::env::var("GH_COMMAND").unwrap_or_else(|_| "gh".to_string()))
+}
+
+/// Error for a gh process that could not be started
+fn spawn_error(error: std::io::Error, action: &str) -> GhReplyError {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        GhReplyError::GhNotInstalled
+    } else {
+        GhReplyError::GhError(format!("Failed to {} gh: {}", action, error))
+    }
+}
+
+/// Error for a gh command that exited with a failure. `gh api graphql`
+/// exits non-zero when the response has errors, but still prints it.
+fn command_error(args: &[&str], stdout: &[u8], stderr: &[u8]) -> GhReplyError {
+    if let Ok(body) = serde_json::from_slice::<Value>(stdout) {
+        if let Some(errors) = body.get("errors") {
+            return graphql_error(errors);
+        }
+    }
+
+    let stderr = String::from_utf8_lossy(stderr);
+    let lower = stderr.to_lowercase();
+    if lower.contains("gh auth login") || lower.contains("bad credentials") || lower.contains("http 401") {
+        let reason = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or("authentication failed");
+        return GhReplyError::AuthError(reason.trim().to_string());
+    }
+    if let Some(err) = http_error(&stderr) {
+        return err;
+    }
+    GhReplyError::GhError(format!("gh command failed: {}\nstderr: {}", args.join(" "), stderr))
+}
+
+/// Classify the stderr of a failed gh request: HTTP 404 and 403 are
+/// permanent, HTTP 5xx and network failures may go away on retry
+fn http_error(stderr: &str) -> Option<GhReplyError> {
+    let lower = stderr.to_lowercase();
+    let status_re = regex::Regex::new(r"\s*\((?i:http) (\d{3})\)").ok()?;
+    let status = status_re.captures(stderr).and_then(|c| c[1].parse::<u16>().ok());
+    let kind = match status {
+        Some(404) => GraphqlErrorKind::NotFound,
+        Some(403) => GraphqlErrorKind::Forbidden,
+        Some(500..=599) => GraphqlErrorKind::Server,
+        Some(_) => return None,
+        None if [
+            "connection refused",
+            "connection reset",
+            "could not resolve host",
+            "no such host",
+            "i/o timeout",
+            "tls handshake timeout",
+            "unexpected eof",
+            "network is unreachable",
+        ]
+        .iter()
+        .any(|pattern| lower.contains(pattern)) =>
+        {
+            GraphqlErrorKind::Network
+        }
+        None => return None,
+    };
+
+    let message = stderr.lines().find(|l| !l.trim().is_empty()).unwrap_or_default().trim();
+    let message = status_re.replace(message.strip_prefix("gh: ").unwrap_or(message), "");
+    Some(GhReplyError::HttpError {
+        kind,
+        status,
+        message: message.to_string(),
+    })
+}
+
+/// Error for a GraphQL response carrying an `errors` array, classified by
+/// its first error
+fn graphql_error(errors: &Value) -> GhReplyError {
+    let errors = errors.as_array().cloned().unwrap_or_else(|| vec![errors.clone()]);
+    let message = errors
+        .iter()
+        .map(|e| e["message"].as_str().map(str::to_string).unwrap_or_else(|| e.to_string()))
+        .collect::<Vec<_>>()
+        .join("; ");
+    let first = errors.first().cloned().unwrap_or(Value::Null);
+    let path = first["path"].as_array().map(|path| {
+        path.iter()
+            .map(|p| p.as_str().map(str::to_string).unwrap_or_else(|| p.to_string()))
+            .collect::<Vec<_>>()
+            .join(".")
+    });
+
+    GhReplyError::GraphqlError {
+        kind: GraphqlErrorKind::classify(&first),
+        message,
+        path,
+        errors,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -451,65 +1064,85 @@ mod tests {
     }
 
     #[test]
-    fn test_ensure_gh_available_success() {
+    fn test_command_error_classifies_stderr() {
+        let args = ["api", "repos/o/r"];
+        let err = command_error(&args, b"", b"gh: Not Found (HTTP 404)\n");
+        assert_eq!((err.kind(), err.exit_code(), err.is_transient()), ("not_found", 5, false));
+        assert_eq!(err.to_string(), "GitHub request failed (HTTP 404): Not Found");
+
+        let err = command_error(&args, b"", b"gh: Resource not accessible by integration (HTTP 403)\n");
+        assert_eq!((err.kind(), err.is_transient()), ("forbidden", false));
+
+        let err = command_error(&args, b"", b"gh: Bad Gateway (HTTP 502)\n");
+        assert_eq!((err.kind(), err.exit_code(), err.is_transient()), ("server_error", 13, true));
+
+        let err = command_error(&args, b"", b"Post \"https://api.github.com/graphql\": dial tcp: connection refused\n");
+        assert_eq!((err.kind(), err.is_transient()), ("network", true));
+
+        let err = command_error(&args, b"", b"unknown flag: --nope\n");
+        assert_eq!((err.kind(), err.is_transient()), ("gh", false));
+    }
+
+    #[tokio::test]
+    async fn test_ensure_gh_available_success() {
         setup_test_env();
-        let result = GhClient::ensure_gh_available();
+        let result = GhClient::ensure_gh_available().await;
         assert!(result.is_ok());
     }
 
-    #[test]
-    fn test_get_repo_info_with_override() {
+    #[tokio::test]
+    async fn test_get_repo_info_with_override() {
         let client = GhClient::new(Some("owner/repo".to_string()));
-        let result = client.get_repo_info();
+        let result = client.get_repo_info().await;
         assert!(result.is_ok());
         let (owner, name) = result.unwrap();
         assert_eq!(owner, "owner");
         assert_eq!(name, "repo");
     }
 
-    #[test]
-    fn test_get_repo_info_invalid_override() {
+    #[tokio::test]
+    async fn test_get_repo_info_invalid_override() {
         let client = GhClient::new(Some("invalid".to_string()));
-        let result = client.get_repo_info();
+        let result = client.get_repo_info().await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Invalid repo override"));
     }
 
-    #[test]
-    fn test_get_repo_info_from_gh() {
+    #[tokio::test]
+    async fn test_get_repo_info_from_gh() {
         setup_test_env();
         let client = GhClient::new(None);
-        let result = client.get_repo_info();
+        let result = client.get_repo_info().await;
         assert!(result.is_ok());
         let (owner, name) = result.unwrap();
         assert_eq!(owner, "test-owner");
         assert_eq!(name, "test-repo");
     }
 
-    #[test]
-    fn test_get_authenticated_user() {
+    #[tokio::test]
+    async fn test_get_authenticated_user() {
         setup_test_env();
         let client = GhClient::new(None);
-        let result = client.get_authenticated_user();
+        let result = client.get_authenticated_user().await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "testuser");
     }
 
-    #[test]
-    fn test_get_pr_details() {
+    #[tokio::test]
+    async fn test_get_pr_details() {
         setup_test_env();
         let client = GhClient::new(None);
-        let result = client.get_pr_details("123");
+        let result = client.get_pr_details("123").await;
         assert!(result.is_ok());
         let details = result.unwrap();
         assert_eq!(details["title"].as_str().unwrap(), "Test PR");
     }
 
-    #[test]
-    fn test_get_pr_details_with_repo_override() {
+    #[tokio::test]
+    async fn test_get_pr_details_with_repo_override() {
         setup_test_env();
         let client = GhClient::new(Some("owner/repo".to_string()));
-        let result = client.get_pr_details("123");
+        let result = client.get_pr_details("123").await;
         assert!(result.is_ok());
     }
 
@@ -533,6 +1166,18 @@ mod tests {
         assert!(response["data"].is_object());
     }
 
+    #[tokio::test]
+    async fn test_post_reply_returns_comment() {
+        setup_test_env();
+        let client = GhClient::new(None);
+        let comment = client.post_reply(123, "PRRT_thread1", "Thanks").await.unwrap();
+        assert_eq!(comment.thread_id, "PRRT_thread1");
+        assert_eq!(comment.id, "PRRC_new1");
+        assert_eq!(comment.database_id, Some(1001));
+        assert!(comment.url.ends_with("#discussion_r1001"));
+        assert_eq!(comment.created_at, "2024-01-01T00:00:00Z");
+    }
+
     #[tokio::test]
     async fn test_gh_graphql_with_variables() {
         setup_test_env();
@@ -1,3 +1,6 @@
+pub mod cache;
 pub mod client;
+pub mod policy;
+pub mod rate_limit;
 
 pub use client::GhClient;
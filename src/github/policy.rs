@@ -0,0 +1,245 @@
+use crate::error::{GhReplyError, Result};
+use crate::git;
+use std::time::Duration;
+
+/// Environment variable setting the per-request timeout
+pub const TIMEOUT_ENV: &str = "GHREPLY_TIMEOUT";
+
+/// Environment variable setting how often a failed query is retried
+pub const RETRIES_ENV: &str = "GHREPLY_RETRIES";
+
+/// Environment variable setting the base delay between retries
+pub const RETRY_DELAY_ENV: &str = "GHREPLY_RETRY_DELAY";
+
+/// Whether a GraphQL document reads or changes data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Query,
+    Mutation,
+}
+
+impl RequestKind {
+    pub fn of(document: &str) -> Self {
+        if document.trim_start().starts_with("mutation") {
+            Self::Mutation
+        } else {
+            Self::Query
+        }
+    }
+}
+
+/// How long a gh request may run and how failed requests are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestPolicy {
+    /// Time after which a hung gh process is killed; `None` waits forever
+    pub timeout: Option<Duration>,
+    /// Retries after the first attempt
+    pub retries: u32,
+    /// Base delay of the exponential backoff between retries
+    pub retry_delay: Duration,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            retries: 3,
+            retry_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RequestPolicy {
+    /// Combine command-line values with `GHREPLY_*` environment variables
+    /// and `git config gh-reply.*`, in that order of precedence. A zero
+    /// timeout disables it.
+    pub async fn resolve(
+        timeout: Option<Duration>,
+        retries: Option<u32>,
+        retry_delay: Option<Duration>,
+    ) -> Result<Self> {
+        Self::resolve_from(timeout, retries, retry_delay, |name| std::env::var(name).ok()).await
+    }
+
+    /// `resolve` with environment variables looked up through `env`
+    pub async fn resolve_from(
+        timeout: Option<Duration>,
+        retries: Option<u32>,
+        retry_delay: Option<Duration>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self> {
+        let default = Self::default();
+        let timeout = match timeout {
+            Some(timeout) => Some(timeout),
+            None => setting_from(&env, TIMEOUT_ENV, "timeout", parse_duration).await?,
+        };
+        let retries = match retries {
+            Some(retries) => retries,
+            None => setting_from(&env, RETRIES_ENV, "retries", |v| v.parse::<u32>().map_err(|e| e.to_string()))
+                .await?
+                .unwrap_or(default.retries),
+        };
+        let retry_delay = match retry_delay {
+            Some(delay) => delay,
+            None => setting_from(&env, RETRY_DELAY_ENV, "retryDelay", parse_duration)
+                .await?
+                .unwrap_or(default.retry_delay),
+        };
+
+        Ok(Self {
+            timeout: timeout.map_or(default.timeout, |t| Some(t).filter(|t| !t.is_zero())),
+            retries,
+            retry_delay,
+        })
+    }
+
+    /// Whether a failed request should be sent again. Queries are retried
+    /// on any transient failure. A mutation is retried only when GitHub
+    /// rejected it for rate limiting: after a timeout or server error it
+    /// may have been applied, and sending it again could post twice.
+    pub fn should_retry(&self, kind: RequestKind, error: &GhReplyError) -> bool {
+        match kind {
+            RequestKind::Query => error.is_transient(),
+            RequestKind::Mutation => matches!(error, GhReplyError::RateLimited { .. }),
+        }
+    }
+
+    /// Delay before retry number `attempt` (from 1): a random duration up to
+    /// `retry_delay * 2^attempt`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let base = u64::try_from(self.retry_delay.as_millis()).unwrap_or(u64::MAX);
+        let max_delay = base.saturating_mul(2_u64.pow(attempt.min(16)));
+        if max_delay == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::random::<u64>() % max_delay)
+    }
+}
+
+/// Read a setting from its environment variable or `git config gh-reply.<key>`
+pub(crate) async fn setting<T>(
+    env: &str,
+    key: &str,
+    parse: impl Fn(&str) -> std::result::Result<T, String>,
+) -> Result<Option<T>> {
+    setting_from(|name| std::env::var(name).ok(), env, key, parse).await
+}
+
+/// `setting` with environment variables looked up through `lookup`
+async fn setting_from<T>(
+    lookup: impl Fn(&str) -> Option<String>,
+    env: &str,
+    key: &str,
+    parse: impl Fn(&str) -> std::result::Result<T, String>,
+) -> Result<Option<T>> {
+    let (source, value) = match lookup(env) {
+        Some(value) => (env.to_string(), value),
+        None => match git::git(&["config", "--get", &format!("gh-reply.{}", key)]).await {
+            Ok(value) => (format!("gh-reply.{}", key), value),
+            Err(_) => return Ok(None),
+        },
+    };
+
+    let value = value.trim();
+    parse(value)
+        .map(Some)
+        .map_err(|e| GhReplyError::ConfigError(format!("Invalid {} value '{}': {}", source, value, e)))
+}
+
+/// Parse a duration such as `30s`, `500ms` or `2m`. A bare number is seconds.
+pub fn parse_duration(value: &str) -> std::result::Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("expected a duration like 30s or 500ms, got '{}'", value))?;
+    let seconds = match unit.trim() {
+        "" | "s" => number,
+        "ms" => number / 1000.0,
+        "m" => number * 60.0,
+        other => return Err(format!("unknown duration unit '{}' (use ms, s or m)", other)),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|e| format!("invalid duration '{}': {}", value, e))
+}
+
+/// Format a duration the way `parse_duration` reads it
+pub fn format_duration(duration: &Duration) -> String {
+    if duration.subsec_millis() == 0 {
+        format!("{}s", duration.as_secs())
+    } else {
+        format!("{}ms", duration.as_millis())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("3h").is_err());
+
+        assert_eq!(format_duration(&Duration::from_secs(30)), "30s");
+        assert_eq!(format_duration(&Duration::from_millis(1500)), "1500ms");
+    }
+
+    #[test]
+    fn test_retry_policies_by_request_kind() {
+        let policy = RequestPolicy::default();
+        let server_error = GhReplyError::GraphqlError {
+            kind: crate::error::GraphqlErrorKind::Server,
+            message: "Something went wrong".to_string(),
+            path: None,
+            errors: vec![],
+        };
+        let rate_limited = GhReplyError::RateLimited {
+            message: "secondary rate limit".to_string(),
+            retry_after: Some(1),
+        };
+
+        assert_eq!(RequestKind::of("query { viewer { login } }"), RequestKind::Query);
+        assert_eq!(RequestKind::of("  mutation($id: ID!) { x }"), RequestKind::Mutation);
+        assert!(policy.should_retry(RequestKind::Query, &server_error));
+        assert!(!policy.should_retry(RequestKind::Mutation, &server_error));
+        assert!(policy.should_retry(RequestKind::Mutation, &rate_limited));
+        assert!(!policy.should_retry(RequestKind::Query, &GhReplyError::InvalidInput("x".to_string())));
+
+        assert!(policy.backoff(1) < Duration::from_secs(1));
+        let no_delay = RequestPolicy { retry_delay: Duration::ZERO, ..policy };
+        assert_eq!(no_delay.backoff(3), Duration::ZERO);
+
+        // A huge base delay saturates instead of overflowing
+        let huge = RequestPolicy { retry_delay: Duration::from_secs(u64::MAX / 1000), ..policy };
+        huge.backoff(16);
+        let max = RequestPolicy { retry_delay: Duration::MAX, ..policy };
+        max.backoff(1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_precedence() {
+        // Environment values are passed in so parallel tests don't see them
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string())
+        };
+
+        let policy = RequestPolicy::resolve_from(None, Some(1), None, env(&[(TIMEOUT_ENV, "5s"), (RETRIES_ENV, "4")]))
+            .await
+            .unwrap();
+        assert_eq!(policy.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(policy.retries, 1);
+
+        let policy = RequestPolicy::resolve_from(None, None, None, env(&[(TIMEOUT_ENV, "0")])).await.unwrap();
+        assert_eq!(policy.timeout, None);
+        assert_eq!(policy.retries, 3);
+
+        let err = RequestPolicy::resolve_from(None, None, None, env(&[(RETRIES_ENV, "many")])).await.unwrap_err();
+        assert!(err.to_string().contains("Invalid GHREPLY_RETRIES value 'many'"));
+    }
+}
@@ -0,0 +1,222 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Below this many remaining points, requests are spaced out so the budget
+/// lasts until the reset
+pub const THROTTLE_BELOW: u64 = 100;
+
+/// Longest pause inserted between two throttled requests
+pub const MAX_THROTTLE_DELAY: Duration = Duration::from_secs(5);
+
+/// Longest wait for a rate limit to reset before giving up
+pub const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// GraphQL rate-limit budget as last reported by GitHub
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: u64,
+    pub reset_at: DateTime<Utc>,
+    /// Points the last query cost
+    pub cost: Option<u64>,
+}
+
+impl RateLimit {
+    /// From the `rateLimit` field of a query response
+    pub fn from_graphql(response: &Value) -> Option<Self> {
+        let rate = &response["data"]["rateLimit"];
+        Some(Self {
+            limit: rate["limit"].as_u64(),
+            remaining: rate["remaining"].as_u64()?,
+            reset_at: rate["resetAt"].as_str()?.parse().ok()?,
+            cost: rate["cost"].as_u64(),
+        })
+    }
+
+    /// From the `x-ratelimit-*` response headers
+    pub fn from_headers(headers: &HashMap<String, String>) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.trim().parse::<u64>().ok());
+        Some(Self {
+            limit: header("x-ratelimit-limit"),
+            remaining: header("x-ratelimit-remaining")?,
+            reset_at: Utc.timestamp_opt(header("x-ratelimit-reset")? as i64, 0).single()?,
+            cost: None,
+        })
+    }
+
+    /// Time left until the budget resets
+    pub fn until_reset(&self, now: DateTime<Utc>) -> Duration {
+        (self.reset_at - now).to_std().unwrap_or_default()
+    }
+
+    /// Pause before the next request: none while the budget is healthy,
+    /// then the time to the reset spread over the remaining points
+    pub fn throttle_delay(&self, now: DateTime<Utc>) -> Option<Duration> {
+        if self.remaining >= THROTTLE_BELOW {
+            return None;
+        }
+        let until_reset = self.until_reset(now);
+        if until_reset.is_zero() {
+            return None;
+        }
+        if self.remaining == 0 {
+            return Some(until_reset);
+        }
+        Some((until_reset / self.remaining as u32).min(MAX_THROTTLE_DELAY))
+    }
+
+    /// One-line summary for `--verbose`
+    pub fn describe(&self) -> String {
+        let limit = self.limit.map(|l| format!("/{}", l)).unwrap_or_default();
+        let cost = self.cost.map(|c| format!(", last query cost {}", c)).unwrap_or_default();
+        format!(
+            "{}{} points remaining, resets at {}{}",
+            self.remaining,
+            limit,
+            self.reset_at.with_timezone(&chrono::Local).format("%H:%M:%S"),
+            cost
+        )
+    }
+}
+
+/// Output of `gh api --include`: status line, headers and body
+#[derive(Debug, Default)]
+pub struct Response {
+    pub status: Option<u16>,
+    /// Header names are lowercased
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn parse(output: &str) -> Self {
+        if !output.starts_with("HTTP/") {
+            return Self {
+                body: output.to_string(),
+                ..Self::default()
+            };
+        }
+
+        let (head, body) = output
+            .split_once("\r\n\r\n")
+            .or_else(|| output.split_once("\n\n"))
+            .unwrap_or((output, ""));
+        let mut lines = head.lines();
+        let status = lines.next().and_then(|l| l.split_whitespace().nth(1)).and_then(|s| s.parse().ok());
+        let headers = lines
+            .filter_map(|l| l.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+
+        Self {
+            status,
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    /// How long GitHub asked us to wait, for secondary rate limits
+    /// (`Retry-After`) and an exhausted primary budget (until the reset)
+    pub fn retry_after(&self, now: DateTime<Utc>) -> Option<Duration> {
+        if let Some(seconds) = self.headers.get("retry-after").and_then(|v| v.trim().parse().ok()) {
+            return Some(Duration::from_secs(seconds));
+        }
+        match RateLimit::from_headers(&self.headers) {
+            Some(rate) if rate.remaining == 0 => Some(rate.until_reset(now)),
+            _ => None,
+        }
+    }
+
+    /// Whether the response is a rate-limit rejection rather than a
+    /// permission error (both use 403)
+    pub fn is_rate_limited(&self, now: DateTime<Utc>) -> bool {
+        let rejected = matches!(self.status, Some(403) | Some(429));
+        rejected && (self.retry_after(now).is_some() || self.body.to_lowercase().contains("rate limit"))
+    }
+}
+
+/// Add `rateLimit { ... }` to the top-level selection of a query so each
+/// response reports its cost and the remaining budget. Mutations cannot
+/// select it, and documents with fragments are returned unchanged since
+/// their last brace may close a fragment.
+pub fn with_rate_limit(query: &str) -> String {
+    let trimmed = query.trim();
+    if trimmed.starts_with("mutation") || trimmed.contains("rateLimit") || trimmed.contains("fragment ") {
+        return trimmed.to_string();
+    }
+    match trimmed.rfind('}') {
+        Some(end) => format!("{} rateLimit {{ limit cost remaining resetAt }} }}", &trimmed[..end]),
+        None => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_include_and_headers() {
+        let output = "HTTP/2.0 200 OK\r\nX-Ratelimit-Limit: 5000\r\nX-Ratelimit-Remaining: 42\r\nX-Ratelimit-Reset: 1700000600\r\n\r\n{\"data\": {}}\n";
+        let response = Response::parse(output);
+        assert_eq!(response.status, Some(200));
+        assert_eq!(response.body.trim(), "{\"data\": {}}");
+
+        let rate = RateLimit::from_headers(&response.headers).unwrap();
+        assert_eq!((rate.limit, rate.remaining, rate.reset_at), (Some(5000), 42, at(600)));
+        assert!(!response.is_rate_limited(at(0)));
+
+        let plain = Response::parse("{\"data\": {}}");
+        assert_eq!((plain.status, plain.body.as_str()), (None, "{\"data\": {}}"));
+    }
+
+    #[test]
+    fn test_rate_limited_responses() {
+        let secondary = Response::parse(
+            "HTTP/2.0 403 Forbidden\nRetry-After: 30\n\n{\"message\": \"You have exceeded a secondary rate limit\"}",
+        );
+        assert!(secondary.is_rate_limited(at(0)));
+        assert_eq!(secondary.retry_after(at(0)), Some(Duration::from_secs(30)));
+
+        let exhausted = Response::parse("HTTP/2.0 403 Forbidden\nX-Ratelimit-Remaining: 0\nX-Ratelimit-Reset: 1700000090\n\n{}");
+        assert_eq!(exhausted.retry_after(at(0)), Some(Duration::from_secs(90)));
+
+        let forbidden = Response::parse("HTTP/2.0 403 Forbidden\nX-Ratelimit-Remaining: 10\n\n{\"message\": \"Resource not accessible\"}");
+        assert!(!forbidden.is_rate_limited(at(0)));
+    }
+
+    #[test]
+    fn test_throttle_delay() {
+        let rate = |remaining| RateLimit {
+            limit: Some(5000),
+            remaining,
+            reset_at: at(100),
+            cost: Some(1),
+        };
+        assert_eq!(rate(4000).throttle_delay(at(0)), None);
+        assert_eq!(rate(50).throttle_delay(at(0)), Some(Duration::from_secs(2)));
+        assert_eq!(rate(10).throttle_delay(at(0)), Some(MAX_THROTTLE_DELAY));
+        assert_eq!(rate(0).throttle_delay(at(0)), Some(Duration::from_secs(100)));
+        assert_eq!(rate(0).throttle_delay(at(200)), None);
+    }
+
+    #[test]
+    fn test_with_rate_limit_and_from_graphql() {
+        assert_eq!(
+            with_rate_limit("query($id: ID!) { node(id: $id) { id } }"),
+            "query($id: ID!) { node(id: $id) { id }  rateLimit { limit cost remaining resetAt } }"
+        );
+        let mutation = "mutation { resolveReviewThread(input: {}) { thread { id } } }";
+        assert_eq!(with_rate_limit(mutation), mutation);
+
+        let response = serde_json::json!({"data": {"rateLimit": {"limit": 5000, "cost": 2, "remaining": 4998, "resetAt": "2023-11-14T22:13:20Z"}}});
+        let rate = RateLimit::from_graphql(&response).unwrap();
+        assert_eq!((rate.cost, rate.remaining, rate.reset_at), (Some(2), 4998, at(0)));
+        assert!(rate.describe().starts_with("4998/5000 points remaining, resets at "));
+    }
+}
@@ -0,0 +1,1121 @@
+//! A small jq-compatible expression evaluator used by `--jq`.
+//!
+//! Supports paths (`.a.b`, `.[0]`, `.[]`, `?`), pipes and commas, array and
+//! object construction, string interpolation, arithmetic, comparisons,
+//! `and`/`or`/`//`, and common builtins such as `select`, `map`, `length`,
+//! `keys`, `has`, `contains`, `test`, `join`, `sort_by` and `to_entries`.
+
+use crate::error::{GhReplyError, Result};
+use serde_json::{Map, Number, Value};
+use std::cmp::Ordering;
+
+/// A parsed jq expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter(Expr);
+
+impl Filter {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = lex(source).map_err(syntax_error)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_pipe().map_err(syntax_error)?;
+        if parser.pos < parser.tokens.len() {
+            return Err(syntax_error(format!(
+                "unexpected {:?}",
+                parser.tokens[parser.pos]
+            )));
+        }
+        Ok(Self(expr))
+    }
+
+    /// Run the filter on `input`, returning every output value
+    pub fn run(&self, input: &Value) -> Result<Vec<Value>> {
+        eval(&self.0, input).map_err(|e| GhReplyError::InvalidInput(format!("--jq: {}", e)))
+    }
+}
+
+fn syntax_error(message: String) -> GhReplyError {
+    GhReplyError::InvalidInput(format!("--jq: syntax error: {}", message))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Ident(String),
+    Str(Vec<StrPart>),
+    Num(f64),
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Pipe,
+    Comma,
+    Colon,
+    Semicolon,
+    Question,
+    Op(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum StrPart {
+    Text(String),
+    /// Source of a `\(...)` interpolation
+    Interp(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Identity,
+    Literal(Value),
+    Str(Vec<StrExpr>),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Try(Box<Expr>),
+    Array(Option<Box<Expr>>),
+    Object(Vec<(Expr, Expr)>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Alt(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum StrExpr {
+    Text(String),
+    Interp(Expr),
+}
+
+fn lex(source: &str) -> std::result::Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '.' => {
+                tokens.push(Token::Dot);
+                i += 1;
+            }
+            '[' => {
+                tokens.push(Token::LBracket);
+                i += 1;
+            }
+            ']' => {
+                tokens.push(Token::RBracket);
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '{' => {
+                tokens.push(Token::LBrace);
+                i += 1;
+            }
+            '}' => {
+                tokens.push(Token::RBrace);
+                i += 1;
+            }
+            '|' => {
+                tokens.push(Token::Pipe);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            ':' => {
+                tokens.push(Token::Colon);
+                i += 1;
+            }
+            ';' => {
+                tokens.push(Token::Semicolon);
+                i += 1;
+            }
+            '?' => {
+                tokens.push(Token::Question);
+                i += 1;
+            }
+            '"' => {
+                let (parts, next) = lex_string(&chars, i + 1)?;
+                tokens.push(Token::Str(parts));
+                i = next;
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                tokens.push(Token::Num(
+                    text.parse()
+                        .map_err(|_| format!("invalid number {}", text))?,
+                ));
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ => {
+                let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+                let op = ["==", "!=", "<=", ">=", "//"]
+                    .into_iter()
+                    .find(|op| *op == two)
+                    .or_else(|| {
+                        ["<", ">", "+", "-", "*", "/", "%"]
+                            .into_iter()
+                            .find(|op| op.starts_with(c))
+                    })
+                    .ok_or_else(|| format!("unexpected character '{}'", c))?;
+                tokens.push(Token::Op(op));
+                i += op.len();
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Lex a string literal body starting after the opening quote
+fn lex_string(chars: &[char], mut i: usize) -> std::result::Result<(Vec<StrPart>, usize), String> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+
+    while i < chars.len() {
+        match chars[i] {
+            '"' => {
+                if !text.is_empty() || parts.is_empty() {
+                    parts.push(StrPart::Text(text));
+                }
+                return Ok((parts, i + 1));
+            }
+            '\\' => {
+                let escaped = *chars.get(i + 1).ok_or("unterminated string")?;
+                i += 2;
+                match escaped {
+                    'n' => text.push('\n'),
+                    't' => text.push('\t'),
+                    'r' => text.push('\r'),
+                    '(' => {
+                        // Find the matching parenthesis of the interpolation
+                        let start = i;
+                        let mut depth = 1;
+                        while i < chars.len() && depth > 0 {
+                            match chars[i] {
+                                '(' => depth += 1,
+                                ')' => depth -= 1,
+                                _ => {}
+                            }
+                            i += 1;
+                        }
+                        if depth > 0 {
+                            return Err("unterminated string interpolation".to_string());
+                        }
+                        if !text.is_empty() {
+                            parts.push(StrPart::Text(std::mem::take(&mut text)));
+                        }
+                        parts.push(StrPart::Interp(chars[start..i - 1].iter().collect()));
+                    }
+                    other => text.push(other),
+                }
+            }
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+type ParseResult = std::result::Result<Expr, String>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> std::result::Result<(), String> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(format!("expected {:?}, found {:?}", token, self.peek()))
+        }
+    }
+
+    fn eat_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(k)) if k == keyword) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_pipe(&mut self) -> ParseResult {
+        let mut left = self.parse_comma()?;
+        while self.eat(&Token::Pipe) {
+            left = Expr::Pipe(Box::new(left), Box::new(self.parse_comma()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_comma(&mut self) -> ParseResult {
+        let mut left = self.parse_alt()?;
+        while self.eat(&Token::Comma) {
+            left = Expr::Comma(Box::new(left), Box::new(self.parse_alt()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_alt(&mut self) -> ParseResult {
+        let left = self.parse_or()?;
+        if self.eat_op(&["//"]).is_some() {
+            // `//` is right-associative
+            return Ok(Expr::Alt(Box::new(left), Box::new(self.parse_alt()?)));
+        }
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> ParseResult {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> ParseResult {
+        let mut left = self.parse_comparison()?;
+        while self.eat_keyword("and") {
+            left = Expr::And(Box::new(left), Box::new(self.parse_comparison()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> ParseResult {
+        let left = self.parse_additive()?;
+        if let Some(op) = self.eat_op(&["==", "!=", "<", "<=", ">", ">="]) {
+            return Ok(Expr::Binary(
+                op,
+                Box::new(left),
+                Box::new(self.parse_additive()?),
+            ));
+        }
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> ParseResult {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(op) = self.eat_op(&["+", "-"]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.parse_multiplicative()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> ParseResult {
+        let mut left = self.parse_postfix()?;
+        while let Some(op) = self.eat_op(&["*", "/", "%"]) {
+            left = Expr::Binary(op, Box::new(left), Box::new(self.parse_postfix()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_postfix(&mut self) -> ParseResult {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.peek() == Some(&Token::Dot) {
+                match self.tokens.get(self.pos + 1) {
+                    Some(Token::Ident(name)) => {
+                        expr = Expr::Field(Box::new(expr), name.clone());
+                        self.pos += 2;
+                    }
+                    Some(Token::Str(parts)) => {
+                        let name = plain_string(parts)?;
+                        expr = Expr::Field(Box::new(expr), name);
+                        self.pos += 2;
+                    }
+                    Some(Token::LBracket) => {
+                        self.pos += 1;
+                        expr = self.parse_bracket(expr)?;
+                    }
+                    _ => return Err("expected a field name after '.'".to_string()),
+                }
+            } else if self.peek() == Some(&Token::LBracket) {
+                expr = self.parse_bracket(expr)?;
+            } else if self.eat(&Token::Question) {
+                expr = Expr::Try(Box::new(expr));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    /// `[]` or `[expr]` applied to `target`
+    fn parse_bracket(&mut self, target: Expr) -> ParseResult {
+        self.expect(&Token::LBracket)?;
+        if self.eat(&Token::RBracket) {
+            return Ok(Expr::Iterate(Box::new(target)));
+        }
+        let index = self.parse_pipe()?;
+        self.expect(&Token::RBracket)?;
+        Ok(Expr::Index(Box::new(target), Box::new(index)))
+    }
+
+    fn parse_primary(&mut self) -> ParseResult {
+        let token = self.peek().cloned().ok_or("unexpected end of expression")?;
+        self.pos += 1;
+        match token {
+            Token::Dot => match self.peek() {
+                Some(Token::Ident(name)) => {
+                    let name = name.clone();
+                    self.pos += 1;
+                    Ok(Expr::Field(Box::new(Expr::Identity), name))
+                }
+                Some(Token::Str(parts)) => {
+                    let name = plain_string(parts)?;
+                    self.pos += 1;
+                    Ok(Expr::Field(Box::new(Expr::Identity), name))
+                }
+                Some(Token::LBracket) => self.parse_bracket(Expr::Identity),
+                _ => Ok(Expr::Identity),
+            },
+            Token::Num(n) => Ok(Expr::Literal(number(n))),
+            Token::Str(parts) => string_expr(&parts),
+            Token::Op("-") => Ok(Expr::Neg(Box::new(self.parse_postfix()?))),
+            Token::LParen => {
+                let expr = self.parse_pipe()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Token::LBracket => {
+                if self.eat(&Token::RBracket) {
+                    return Ok(Expr::Array(None));
+                }
+                let expr = self.parse_pipe()?;
+                self.expect(&Token::RBracket)?;
+                Ok(Expr::Array(Some(Box::new(expr))))
+            }
+            Token::LBrace => self.parse_object(),
+            Token::Ident(name) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ => {
+                    let mut args = Vec::new();
+                    if self.eat(&Token::LParen) {
+                        loop {
+                            args.push(self.parse_pipe()?);
+                            if !self.eat(&Token::Semicolon) {
+                                break;
+                            }
+                        }
+                        self.expect(&Token::RParen)?;
+                    }
+                    Ok(Expr::Call(name, args))
+                }
+            },
+            other => Err(format!("unexpected {:?}", other)),
+        }
+    }
+
+    fn parse_object(&mut self) -> ParseResult {
+        let mut entries = Vec::new();
+        if self.eat(&Token::RBrace) {
+            return Ok(Expr::Object(entries));
+        }
+        loop {
+            let token = self.peek().cloned().ok_or("unterminated object")?;
+            self.pos += 1;
+            let (key, shorthand) = match token {
+                Token::Ident(name) => (Expr::Literal(Value::String(name.clone())), Some(name)),
+                Token::Str(parts) => {
+                    let name = plain_string(&parts).ok();
+                    (string_expr(&parts)?, name)
+                }
+                Token::LParen => {
+                    let key = self.parse_pipe()?;
+                    self.expect(&Token::RParen)?;
+                    (key, None)
+                }
+                other => return Err(format!("unexpected {:?} in object", other)),
+            };
+
+            let value = if self.eat(&Token::Colon) {
+                self.parse_alt()?
+            } else {
+                // `{id}` is short for `{id: .id}`
+                let name = shorthand.ok_or("object key needs a value")?;
+                Expr::Field(Box::new(Expr::Identity), name)
+            };
+            entries.push((key, value));
+
+            if self.eat(&Token::RBrace) {
+                return Ok(Expr::Object(entries));
+            }
+            self.expect(&Token::Comma)?;
+        }
+    }
+}
+
+fn plain_string(parts: &[StrPart]) -> std::result::Result<String, String> {
+    match parts {
+        [StrPart::Text(text)] => Ok(text.clone()),
+        _ => Err("interpolated strings are not allowed here".to_string()),
+    }
+}
+
+fn string_expr(parts: &[StrPart]) -> ParseResult {
+    if let Ok(text) = plain_string(parts) {
+        return Ok(Expr::Literal(Value::String(text)));
+    }
+    let mut exprs = Vec::new();
+    for part in parts {
+        exprs.push(match part {
+            StrPart::Text(text) => StrExpr::Text(text.clone()),
+            StrPart::Interp(source) => {
+                let tokens = lex(source)?;
+                let mut parser = Parser { tokens, pos: 0 };
+                StrExpr::Interp(parser.parse_pipe()?)
+            }
+        });
+    }
+    Ok(Expr::Str(exprs))
+}
+
+fn number(n: f64) -> Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        Value::Number(Number::from(n as i64))
+    } else {
+        Number::from_f64(n)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// jq's total order: null < false < true < numbers < strings < arrays < objects
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .unwrap_or(0.0)
+            .partial_cmp(&y.as_f64().unwrap_or(0.0))
+            .unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Array(x), Value::Array(y)) => x
+            .iter()
+            .zip(y)
+            .map(|(a, b)| compare(a, b))
+            .find(|o| *o != Ordering::Equal)
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Object(x), Value::Object(y)) => {
+            let keys = |m: &Map<String, Value>| m.keys().cloned().collect::<Vec<_>>();
+            keys(x).cmp(&keys(y)).then_with(|| {
+                x.values()
+                    .zip(y.values())
+                    .map(|(a, b)| compare(a, b))
+                    .find(|o| *o != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn contains(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(x), Value::String(y)) => x.contains(y.as_str()),
+        (Value::Array(x), Value::Array(y)) => y.iter().all(|b| x.iter().any(|a| contains(a, b))),
+        (Value::Object(x), Value::Object(y)) => y
+            .iter()
+            .all(|(k, b)| x.get(k).is_some_and(|a| contains(a, b))),
+        _ => a == b,
+    }
+}
+
+type EvalResult = std::result::Result<Vec<Value>, String>;
+
+fn eval(expr: &Expr, input: &Value) -> EvalResult {
+    match expr {
+        Expr::Identity => Ok(vec![input.clone()]),
+        Expr::Literal(value) => Ok(vec![value.clone()]),
+        Expr::Str(parts) => {
+            let mut results = vec![String::new()];
+            for part in parts {
+                match part {
+                    StrExpr::Text(text) => results.iter_mut().for_each(|r| r.push_str(text)),
+                    StrExpr::Interp(expr) => {
+                        let values = eval(expr, input)?;
+                        results = results
+                            .iter()
+                            .flat_map(|r| {
+                                values.iter().map(move |v| format!("{}{}", r, to_text(v)))
+                            })
+                            .collect();
+                    }
+                }
+            }
+            Ok(results.into_iter().map(Value::String).collect())
+        }
+        Expr::Field(target, name) => eval(target, input)?
+            .iter()
+            .map(|value| match value {
+                Value::Object(map) => Ok(map.get(name).cloned().unwrap_or(Value::Null)),
+                Value::Null => Ok(Value::Null),
+                other => Err(format!(
+                    "Cannot index {} with \"{}\"",
+                    type_name(other),
+                    name
+                )),
+            })
+            .collect(),
+        Expr::Index(target, index) => {
+            let mut out = Vec::new();
+            for value in eval(target, input)? {
+                for index in eval(index, input)? {
+                    out.push(match (&value, &index) {
+                        (Value::Object(map), Value::String(key)) => {
+                            map.get(key).cloned().unwrap_or(Value::Null)
+                        }
+                        (Value::Array(items), Value::Number(n)) => {
+                            let n = n.as_f64().unwrap_or(0.0) as i64;
+                            let i = if n < 0 { items.len() as i64 + n } else { n };
+                            usize::try_from(i)
+                                .ok()
+                                .and_then(|i| items.get(i))
+                                .cloned()
+                                .unwrap_or(Value::Null)
+                        }
+                        (Value::Null, _) => Value::Null,
+                        (value, index) => {
+                            return Err(format!(
+                                "Cannot index {} with {}",
+                                type_name(value),
+                                type_name(index)
+                            ))
+                        }
+                    });
+                }
+            }
+            Ok(out)
+        }
+        Expr::Iterate(target) => {
+            let mut out = Vec::new();
+            for value in eval(target, input)? {
+                match value {
+                    Value::Array(items) => out.extend(items),
+                    Value::Object(map) => out.extend(map.into_iter().map(|(_, v)| v)),
+                    other => return Err(format!("Cannot iterate over {}", type_name(&other))),
+                }
+            }
+            Ok(out)
+        }
+        Expr::Try(inner) => Ok(eval(inner, input).unwrap_or_default()),
+        Expr::Array(inner) => Ok(vec![Value::Array(match inner {
+            Some(inner) => eval(inner, input)?,
+            None => Vec::new(),
+        })]),
+        Expr::Object(entries) => {
+            let mut objects = vec![Map::new()];
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut next = Vec::new();
+                for object in &objects {
+                    for key in &keys {
+                        let Value::String(key) = key else {
+                            return Err(format!(
+                                "Object keys must be strings, not {}",
+                                type_name(key)
+                            ));
+                        };
+                        for value in &values {
+                            let mut object = object.clone();
+                            object.insert(key.clone(), value.clone());
+                            next.push(object);
+                        }
+                    }
+                }
+                objects = next;
+            }
+            Ok(objects.into_iter().map(Value::Object).collect())
+        }
+        Expr::Pipe(left, right) => {
+            let mut out = Vec::new();
+            for value in eval(left, input)? {
+                out.extend(eval(right, &value)?);
+            }
+            Ok(out)
+        }
+        Expr::Comma(left, right) => {
+            let mut out = eval(left, input)?;
+            out.extend(eval(right, input)?);
+            Ok(out)
+        }
+        Expr::Alt(left, right) => {
+            let values: Vec<Value> = eval(left, input)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(truthy)
+                .collect();
+            if values.is_empty() {
+                eval(right, input)
+            } else {
+                Ok(values)
+            }
+        }
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            let is_and = matches!(expr, Expr::And(..));
+            let mut out = Vec::new();
+            for l in eval(left, input)? {
+                if truthy(&l) != is_and {
+                    out.push(Value::Bool(!is_and));
+                    continue;
+                }
+                for r in eval(right, input)? {
+                    out.push(Value::Bool(truthy(&r)));
+                }
+            }
+            Ok(out)
+        }
+        Expr::Neg(inner) => eval(inner, input)?
+            .iter()
+            .map(|v| match v.as_f64() {
+                Some(n) => Ok(number(-n)),
+                None => Err(format!("{} cannot be negated", type_name(v))),
+            })
+            .collect(),
+        Expr::Binary(op, left, right) => {
+            let mut out = Vec::new();
+            for r in eval(right, input)? {
+                for l in eval(left, input)? {
+                    out.push(binary(op, &l, &r)?);
+                }
+            }
+            Ok(out)
+        }
+        Expr::Call(name, args) => call(name, args, input),
+    }
+}
+
+fn binary(op: &str, l: &Value, r: &Value) -> std::result::Result<Value, String> {
+    let ordering = compare(l, r);
+    Ok(match op {
+        "==" => Value::Bool(ordering == Ordering::Equal),
+        "!=" => Value::Bool(ordering != Ordering::Equal),
+        "<" => Value::Bool(ordering == Ordering::Less),
+        "<=" => Value::Bool(ordering != Ordering::Greater),
+        ">" => Value::Bool(ordering == Ordering::Greater),
+        ">=" => Value::Bool(ordering != Ordering::Less),
+        _ => match (op, l, r) {
+            ("+", Value::Null, v) | ("+", v, Value::Null) => v.clone(),
+            ("+", Value::String(a), Value::String(b)) => Value::String(format!("{}{}", a, b)),
+            ("+", Value::Array(a), Value::Array(b)) => {
+                Value::Array(a.iter().chain(b).cloned().collect())
+            }
+            ("+", Value::Object(a), Value::Object(b)) => {
+                let mut merged = a.clone();
+                merged.extend(b.clone());
+                Value::Object(merged)
+            }
+            ("-", Value::Array(a), Value::Array(b)) => {
+                Value::Array(a.iter().filter(|v| !b.contains(v)).cloned().collect())
+            }
+            (op, Value::Number(a), Value::Number(b)) => {
+                let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+                match op {
+                    "+" => number(a + b),
+                    "-" => number(a - b),
+                    "*" => number(a * b),
+                    "/" if b == 0.0 => return Err("division by zero".to_string()),
+                    "/" => number(a / b),
+                    _ => match (a as i64, b as i64) {
+                        (_, 0) => return Err("modulo by zero".to_string()),
+                        (a, b) => number(a.wrapping_rem(b) as f64),
+                    },
+                }
+            }
+            (op, l, r) => {
+                return Err(format!(
+                    "{} and {} cannot be combined with {}",
+                    type_name(l),
+                    type_name(r),
+                    op
+                ))
+            }
+        },
+    })
+}
+
+/// Text of a value inside a string interpolation or `tostring`
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Evaluate the single argument of a builtin
+fn arg_values(args: &[Expr], input: &Value, name: &str) -> EvalResult {
+    match args {
+        [arg] => eval(arg, input),
+        _ => Err(format!("{}/{} is not defined", name, args.len())),
+    }
+}
+
+fn call(name: &str, args: &[Expr], input: &Value) -> EvalResult {
+    let string_fn = |f: &dyn Fn(&str, &str) -> Value| -> EvalResult {
+        let Value::String(s) = input else {
+            return Err(format!("{} cannot be used with {}", name, type_name(input)));
+        };
+        arg_values(args, input, name)?
+            .iter()
+            .map(|a| match a {
+                Value::String(a) => Ok(f(s, a)),
+                other => Err(format!(
+                    "{} needs a string argument, not {}",
+                    name,
+                    type_name(other)
+                )),
+            })
+            .collect()
+    };
+
+    let one = |value: Value| Ok(vec![value]);
+    match (name, args.len()) {
+        ("empty", 0) => Ok(Vec::new()),
+        ("not", 0) => one(Value::Bool(!truthy(input))),
+        ("type", 0) => one(Value::String(type_name(input).to_string())),
+        ("length", 0) => one(match input {
+            Value::Null => number(0.0),
+            Value::Bool(_) => return Err("boolean has no length".to_string()),
+            Value::Number(n) => number(n.as_f64().unwrap_or(0.0).abs()),
+            Value::String(s) => number(s.chars().count() as f64),
+            Value::Array(a) => number(a.len() as f64),
+            Value::Object(o) => number(o.len() as f64),
+        }),
+        ("keys", 0) => one(match input {
+            Value::Object(o) => Value::Array(o.keys().map(|k| Value::String(k.clone())).collect()),
+            Value::Array(a) => Value::Array((0..a.len()).map(|i| number(i as f64)).collect()),
+            other => return Err(format!("{} has no keys", type_name(other))),
+        }),
+        ("tostring", 0) => one(Value::String(to_text(input))),
+        ("tonumber", 0) => one(match input {
+            Value::Number(_) => input.clone(),
+            Value::String(s) => number(
+                s.trim()
+                    .parse()
+                    .map_err(|_| format!("Cannot parse '{}' as a number", s))?,
+            ),
+            other => return Err(format!("{} cannot be parsed as a number", type_name(other))),
+        }),
+        ("ascii_downcase", 0) | ("ascii_upcase", 0) => match input {
+            Value::String(s) if name == "ascii_downcase" => {
+                one(Value::String(s.to_ascii_lowercase()))
+            }
+            Value::String(s) => one(Value::String(s.to_ascii_uppercase())),
+            other => Err(format!("{} cannot be used with {}", name, type_name(other))),
+        },
+        ("first", 0) => eval(
+            &Expr::Index(
+                Box::new(Expr::Identity),
+                Box::new(Expr::Literal(number(0.0))),
+            ),
+            input,
+        ),
+        ("last", 0) => eval(
+            &Expr::Index(
+                Box::new(Expr::Identity),
+                Box::new(Expr::Literal(number(-1.0))),
+            ),
+            input,
+        ),
+        ("first", 1) => Ok(eval(&args[0], input)?.into_iter().take(1).collect()),
+        ("select", 1) => Ok(eval(&args[0], input)?
+            .iter()
+            .filter(|v| truthy(v))
+            .map(|_| input.clone())
+            .collect()),
+        ("map", 1) => eval(
+            &Expr::Array(Some(Box::new(Expr::Pipe(
+                Box::new(Expr::Iterate(Box::new(Expr::Identity))),
+                Box::new(args[0].clone()),
+            )))),
+            input,
+        ),
+        ("has", 1) => arg_values(args, input, name)?
+            .iter()
+            .map(|key| match (input, key) {
+                (Value::Object(o), Value::String(k)) => Ok(Value::Bool(o.contains_key(k))),
+                (Value::Array(a), Value::Number(n)) => {
+                    Ok(Value::Bool((n.as_f64().unwrap_or(-1.0) as usize) < a.len()))
+                }
+                (v, k) => Err(format!(
+                    "Cannot check whether {} has a {} key",
+                    type_name(v),
+                    type_name(k)
+                )),
+            })
+            .collect(),
+        ("contains", 1) => Ok(arg_values(args, input, name)?
+            .iter()
+            .map(|b| Value::Bool(contains(input, b)))
+            .collect()),
+        ("startswith", 1) => string_fn(&|s, a| Value::Bool(s.starts_with(a))),
+        ("endswith", 1) => string_fn(&|s, a| Value::Bool(s.ends_with(a))),
+        ("ltrimstr", 1) => {
+            string_fn(&|s, a| Value::String(s.strip_prefix(a).unwrap_or(s).to_string()))
+        }
+        ("rtrimstr", 1) => {
+            string_fn(&|s, a| Value::String(s.strip_suffix(a).unwrap_or(s).to_string()))
+        }
+        ("split", 1) => string_fn(&|s, a| {
+            Value::Array(s.split(a).map(|p| Value::String(p.to_string())).collect())
+        }),
+        ("test", 1) => {
+            let Value::String(s) = input else {
+                return Err(format!("test cannot be used with {}", type_name(input)));
+            };
+            arg_values(args, input, name)?
+                .iter()
+                .map(|re| {
+                    let re = re.as_str().ok_or("test needs a string pattern")?;
+                    let re = regex::Regex::new(re).map_err(|e| format!("invalid regex: {}", e))?;
+                    Ok(Value::Bool(re.is_match(s)))
+                })
+                .collect()
+        }
+        ("join", 1) => {
+            let Value::Array(items) = input else {
+                return Err(format!("Cannot join {}", type_name(input)));
+            };
+            arg_values(args, input, name)?
+                .iter()
+                .map(|sep| {
+                    let sep = sep.as_str().ok_or("join needs a string separator")?;
+                    let parts: Vec<String> = items
+                        .iter()
+                        .map(|v| {
+                            if v.is_null() {
+                                String::new()
+                            } else {
+                                to_text(v)
+                            }
+                        })
+                        .collect();
+                    Ok(Value::String(parts.join(sep)))
+                })
+                .collect()
+        }
+        ("add", 0) => {
+            let items = array_items(input, name)?;
+            let mut total = Value::Null;
+            for item in items {
+                total = binary("+", &total, item)?;
+            }
+            one(total)
+        }
+        ("any", 0) => one(Value::Bool(array_items(input, name)?.iter().any(truthy))),
+        ("all", 0) => one(Value::Bool(array_items(input, name)?.iter().all(truthy))),
+        ("reverse", 0) => one(Value::Array(
+            array_items(input, name)?.iter().rev().cloned().collect(),
+        )),
+        ("sort", 0) | ("unique", 0) | ("min", 0) | ("max", 0) => {
+            let mut items = array_items(input, name)?.to_vec();
+            items.sort_by(compare);
+            match name {
+                "unique" => {
+                    items.dedup();
+                    one(Value::Array(items))
+                }
+                "min" => one(items.first().cloned().unwrap_or(Value::Null)),
+                "max" => one(items.last().cloned().unwrap_or(Value::Null)),
+                _ => one(Value::Array(items)),
+            }
+        }
+        ("sort_by", 1) => {
+            let mut keyed = Vec::new();
+            for item in array_items(input, name)? {
+                keyed.push((Value::Array(eval(&args[0], item)?), item.clone()));
+            }
+            keyed.sort_by(|a, b| compare(&a.0, &b.0));
+            one(Value::Array(keyed.into_iter().map(|(_, v)| v).collect()))
+        }
+        ("to_entries", 0) => match input {
+            Value::Object(o) => one(Value::Array(
+                o.iter()
+                    .map(|(k, v)| {
+                        let mut entry = Map::new();
+                        entry.insert("key".to_string(), Value::String(k.clone()));
+                        entry.insert("value".to_string(), v.clone());
+                        Value::Object(entry)
+                    })
+                    .collect(),
+            )),
+            other => Err(format!("{} has no keys", type_name(other))),
+        },
+        _ => Err(format!("{}/{} is not defined", name, args.len())),
+    }
+}
+
+fn array_items<'a>(value: &'a Value, name: &str) -> std::result::Result<&'a [Value], String> {
+    match value {
+        Value::Array(items) => Ok(items),
+        other => Err(format!("{} cannot be used with {}", name, type_name(other))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn run(expr: &str, input: Value) -> Vec<Value> {
+        Filter::parse(expr).unwrap().run(&input).unwrap()
+    }
+
+    fn threads() -> Value {
+        json!([
+            {"id": "T1", "path": "src/main.rs", "line": 10, "is_resolved": false, "author": "reviewer"},
+            {"id": "T2", "path": "src/lib.rs", "line": 5, "is_resolved": true, "author": "bot"},
+            {"id": "T3", "path": "README.md", "line": null, "is_resolved": false, "author": "bot"},
+        ])
+    }
+
+    #[test]
+    fn test_paths_and_iteration() {
+        assert_eq!(run(".[0].id", threads()), vec![json!("T1")]);
+        assert_eq!(run(".[-1].path", threads()), vec![json!("README.md")]);
+        assert_eq!(
+            run(".[].id", threads()),
+            vec![json!("T1"), json!("T2"), json!("T3")]
+        );
+        assert_eq!(run(".[0] | .missing.deeper", threads()), vec![Value::Null]);
+        assert_eq!(run("length", threads()), vec![json!(3)]);
+    }
+
+    #[test]
+    fn test_select_and_map() {
+        assert_eq!(
+            run("[.[] | select(.is_resolved | not) | .id]", threads()),
+            vec![json!(["T1", "T3"])]
+        );
+        assert_eq!(
+            run("map(select(.author == \"bot\")) | length", threads()),
+            vec![json!(2)]
+        );
+        assert_eq!(run("map(.line // 0) | add", threads()), vec![json!(15)]);
+        assert_eq!(
+            run(
+                "[.[] | select(.path | startswith(\"src/\") and (. | test(\"main\")))] | map(.id)",
+                threads()
+            ),
+            vec![json!(["T1"])]
+        );
+    }
+
+    #[test]
+    fn test_construction_and_interpolation() {
+        assert_eq!(
+            run(".[0] | {id, where: \"\\(.path):\\(.line)\"}", threads()),
+            vec![json!({"id": "T1", "where": "src/main.rs:10"})]
+        );
+        assert_eq!(
+            run("[.[].author] | unique | join(\",\")", threads()),
+            vec![json!("bot,reviewer")]
+        );
+        assert_eq!(
+            run("sort_by(.path) | .[0].id", threads()),
+            vec![json!("T3")]
+        );
+        assert_eq!(run("1 + 2 * 3, -1", Value::Null), vec![json!(7), json!(-1)]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Filter::parse(".[").is_err());
+        assert!(Filter::parse("\"abc").is_err());
+        assert!(Filter::parse(".a | nosuchfn")
+            .unwrap()
+            .run(&json!({}))
+            .is_err());
+        let err = Filter::parse(".[0].id").unwrap().run(&json!("text")).unwrap_err();
+        assert_eq!((err.kind(), err.exit_code()), ("invalid_input", 2));
+        assert!(err.to_string().starts_with("Invalid input: --jq: "), "{}", err);
+        let err = Filter::parse(".[").unwrap_err();
+        assert_eq!((err.kind(), err.exit_code()), ("invalid_input", 2));
+        assert_eq!(run(".[0].id?", json!("text")), Vec::<Value>::new());
+
+        for expr in ["5 % 0", "5 % 0.4", "(-1) % 0.5"] {
+            let err = Filter::parse(expr).unwrap().run(&json!(null)).unwrap_err();
+            assert!(err.to_string().contains("modulo by zero"), "{}", expr);
+        }
+        assert_eq!(run("5 % 2.5", json!(null)), vec![json!(1)]);
+        assert_eq!(run("-9223372036854775808 % -1", json!(null)), vec![json!(0)]);
+    }
+}
@@ -2,8 +2,17 @@
 pub mod cli;
 pub mod commands;
 pub mod context;
+pub mod editor;
 pub mod error;
+pub mod filter;
+pub mod git;
+pub mod jq;
 pub mod github;
+pub mod output;
+pub mod policy;
+pub mod snippet;
 pub mod store;
+pub mod suggestion;
+pub mod trailers;
 pub mod types;
 pub mod vars;
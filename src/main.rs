@@ -1,21 +1,67 @@
-mod cli;
-mod commands;
-mod context;
-mod error;
-mod github;
-mod store;
-mod types;
-mod vars;
-
-use clap::Parser;
-use cli::{Cli, Commands};
-use error::Result;
+use clap::error::ErrorKind;
+use clap::{Parser, ValueEnum};
+use gh_reply::cli::{Cli, Commands};
+use gh_reply::commands;
+use gh_reply::error::{self, ErrorFormat, GhReplyError, Result};
+use gh_reply::github;
+use gh_reply::jq::Filter;
+use gh_reply::output;
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+async fn main() {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        // --help is printed by clap as usual
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) => {
+            let format = error_format_from_args();
+            if e.kind() == ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand && format == ErrorFormat::Text {
+                e.exit();
+            }
+            let message = match e.kind() {
+                ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand => "a subcommand is required".to_string(),
+                _ => e.render().to_string().trim().trim_start_matches("error: ").to_string(),
+            };
+            let e = GhReplyError::InvalidInput(message);
+            error::report(&e, format);
+            std::process::exit(e.exit_code());
+        }
+    };
+    if let Err(e) = run(&cli).await {
+        error::report(&e, cli.error_format);
+        std::process::exit(e.exit_code());
+    }
+}
 
-    let client = github::GhClient::new(cli.repo);
+/// `--error-format` as given on the command line, for reporting errors in
+/// the arguments themselves
+fn error_format_from_args() -> ErrorFormat {
+    let args: Vec<String> = std::env::args().collect();
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.strip_prefix("--error-format") {
+            Some("") => args.get(i + 1).cloned(),
+            Some(value) => value.strip_prefix('=').map(str::to_string),
+            None => None,
+        })
+        .and_then(|value| ErrorFormat::from_str(&value, true).ok())
+        .unwrap_or_default()
+}
+
+async fn run(cli: &Cli) -> Result<()> {
+    output::configure(output::OutputOptions {
+        format: cli.format,
+        fields: cli.json.clone(),
+        jq: cli.jq.as_deref().map(Filter::parse).transpose()?,
+        template: cli.template.clone(),
+    });
+
+    let policy = github::policy::RequestPolicy::resolve(cli.timeout, cli.retries, cli.retry_delay).await?;
+    let cache = github::cache::Cache::open(cli.no_cache).await?;
+    let client = github::GhClient::new(cli.repo.clone())
+        .policy(policy)
+        .cache(cache)
+        .verbose(cli.verbose);
 
     match &cli.command {
         Commands::Comment { action } => {
@@ -24,6 +70,12 @@ async fn main() -> Result<()> {
         Commands::List { state } => {
             commands::run_list_command(&client, state).await?;
         }
+        Commands::Tui { pr_number } => {
+            commands::run_tui_command(&client, *pr_number).await?;
+        }
+        Commands::Walk { pr_number, from, snippets } => {
+            commands::run_walk_command(&client, *pr_number, from.as_deref(), snippets).await?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,514 @@
+use crate::error::{GhReplyError, Result};
+use crate::jq::Filter;
+use crate::vars::TemplateExpander;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// Output format selected with the global `--format` option
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON
+    Json,
+    /// One compact JSON value per line
+    Ndjson,
+    /// Aligned columns fitted to the terminal width
+    Table,
+    /// GitHub-flavored markdown table
+    Markdown,
+    /// Tab-separated values without a header
+    Plain,
+    /// `path:line:col: message` lines for editor quickfix lists
+    Quickfix,
+}
+
+/// Global output options taken from the command line
+#[derive(Debug, Default)]
+pub struct OutputOptions {
+    /// `--format`
+    pub format: Option<OutputFormat>,
+    /// `--json`: fields kept in each row, in column order
+    pub fields: Option<Vec<String>>,
+    /// `--jq`: filter applied to the (projected) JSON output
+    pub jq: Option<Filter>,
+    /// `--template`: Go-like template rendered over the (projected) JSON output
+    pub template: Option<String>,
+}
+
+static OPTIONS: OnceLock<OutputOptions> = OnceLock::new();
+
+/// Columns shown by the table formats when present, in this order
+const PREFERRED_COLUMNS: &[&str] = &[
+    "number", "title", "state", "id", "path", "line", "author", "last_author", "body", "resolve", "url",
+];
+
+/// Fields that mark a row as a resolved thread
+const RESOLVED_FIELDS: &[&str] = &["is_resolved", "isResolved"];
+
+/// Narrowest a column is truncated to when fitting the terminal
+const MIN_COLUMN_WIDTH: usize = 8;
+
+/// Set the options chosen on the command line (called once at startup)
+pub fn configure(options: OutputOptions) {
+    OPTIONS.set(options).ok();
+}
+
+/// Print a command result; JSON unless another format was selected
+pub fn print<T: Serialize>(value: &T) -> Result<()> {
+    emit(value, OutputFormat::Json)
+}
+
+/// Print the result of a listing command: a table at the terminal,
+/// JSON when stdout is piped, unless a format was selected
+pub fn print_listing<T: Serialize>(value: &T) -> Result<()> {
+    let default = if std::io::stdout().is_terminal() {
+        OutputFormat::Table
+    } else {
+        OutputFormat::Json
+    };
+    emit(value, default)
+}
+
+fn emit<T: Serialize>(value: &T, default: OutputFormat) -> Result<()> {
+    let options = OPTIONS.get_or_init(OutputOptions::default);
+    let mut value = serde_json::to_value(value)?;
+    if let Some(fields) = &options.fields {
+        value = project(&value, fields)?;
+    }
+
+    // Like `gh --jq`, strings are printed raw and the format is ignored
+    if let Some(filter) = &options.jq {
+        for result in filter.run(&value)? {
+            match result {
+                Value::String(s) => println!("{}", s),
+                other => println!("{}", serde_json::to_string_pretty(&other)?),
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(template) = &options.template {
+        print!("{}", TemplateExpander::new().render_value(&unescape(template), &value)?);
+        return Ok(());
+    }
+
+    // `--json` alone prints JSON even at a terminal, as `gh` does
+    let default = if options.fields.is_some() { OutputFormat::Json } else { default };
+    let format = options.format.unwrap_or(default);
+    let rendered = render(&value, format, terminal_width(), options.fields.as_deref())?;
+    print!("{}", rendered);
+    Ok(())
+}
+
+/// Turn `\n` and `\t` typed in a shell-quoted template into real characters
+fn unescape(template: &str) -> String {
+    template.replace("\\n", "\n").replace("\\t", "\t")
+}
+
+/// Keep only `fields` of each row, or of a single object, as `gh --json`
+/// does. Listings become a plain array of rows.
+pub fn project(value: &Value, fields: &[String]) -> Result<Value> {
+    let (rows, single) = match split_rows(value) {
+        (Some(rows), _) => (rows, false),
+        (None, _) => match value {
+            Value::Object(map) => (vec![map.clone()], true),
+            _ => return Ok(value.clone()),
+        },
+    };
+
+    let mut available: Vec<&String> = rows.iter().flat_map(|row| row.keys()).collect();
+    available.sort();
+    available.dedup();
+    if !rows.is_empty() {
+        if let Some(unknown) = fields.iter().find(|f| !available.contains(f)) {
+            let list: Vec<&str> = available.iter().map(|f| f.as_str()).collect();
+            return Err(GhReplyError::InvalidInput(format!(
+                "Unknown JSON field: \"{}\"\nAvailable fields:\n  {}",
+                unknown,
+                list.join("\n  ")
+            )));
+        }
+    }
+
+    let mut projected: Vec<Value> = rows
+        .iter()
+        .map(|row| {
+            Value::Object(
+                fields
+                    .iter()
+                    .map(|f| (f.clone(), row.get(f).cloned().unwrap_or(Value::Null)))
+                    .collect(),
+            )
+        })
+        .collect();
+    if single {
+        return Ok(projected.remove(0));
+    }
+    Ok(Value::Array(projected))
+}
+
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|c| c.parse().ok())
+        .or_else(|| ratatui::crossterm::terminal::size().ok().map(|(w, _)| w as usize))
+        .unwrap_or(120)
+}
+
+/// Render `value` in `format`; `width` is the terminal width for tables and
+/// `columns` overrides the table columns (from `--json`)
+pub fn render(value: &Value, format: OutputFormat, width: usize, columns: Option<&[String]>) -> Result<String> {
+    if format == OutputFormat::Json {
+        return Ok(format!("{}\n", serde_json::to_string_pretty(value)?));
+    }
+
+    let (rows, summary) = split_rows(value);
+    let mut out = String::new();
+    match format {
+        OutputFormat::Json => unreachable!(),
+        OutputFormat::Ndjson => match rows {
+            Some(rows) => {
+                for row in rows {
+                    out.push_str(&serde_json::to_string(&Value::Object(row))?);
+                    out.push('\n');
+                }
+            }
+            None => {
+                out.push_str(&serde_json::to_string(value)?);
+                out.push('\n');
+            }
+        },
+        OutputFormat::Quickfix => {
+            // A single thread (`comment show`) is one entry
+            let rows = rows.unwrap_or_else(|| match value {
+                Value::Object(map) => vec![map.clone()],
+                _ => Vec::new(),
+            });
+            for row in rows {
+                if let Some(line) = quickfix_line(&row) {
+                    out.push_str(&line);
+                    out.push('\n');
+                }
+            }
+        }
+        OutputFormat::Table | OutputFormat::Markdown | OutputFormat::Plain => {
+            let rows = rows.unwrap_or_else(|| key_value_rows(value));
+            let (headers, cells) = table_cells(&rows, format == OutputFormat::Markdown, columns);
+            match format {
+                OutputFormat::Table => out.push_str(&render_table(&headers, &cells, width)),
+                OutputFormat::Markdown => out.push_str(&render_markdown(&headers, &cells)),
+                _ => {
+                    for row in &cells {
+                        out.push_str(&row.join("\t"));
+                        out.push('\n');
+                    }
+                }
+            }
+            if format != OutputFormat::Plain && !summary.is_empty() {
+                out.push('\n');
+                out.push_str(&summary.join("  "));
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Find the list a value carries: the value itself if it is an array, or the
+/// first field holding an array or a map of objects (e.g. drafts keyed by
+/// thread). Remaining scalar fields are returned as `key: value` summaries.
+fn split_rows(value: &Value) -> (Option<Vec<Map<String, Value>>>, Vec<String>) {
+    let to_row = |item: &Value| match item {
+        Value::Object(map) => map.clone(),
+        other => Map::from_iter([("value".to_string(), other.clone())]),
+    };
+
+    match value {
+        Value::Array(items) => (Some(items.iter().map(to_row).collect()), Vec::new()),
+        Value::Object(map) => {
+            let mut rows = None;
+            let mut summary = Vec::new();
+            for (key, field) in map {
+                match field {
+                    Value::Array(items) if rows.is_none() => rows = Some(items.iter().map(to_row).collect()),
+                    Value::Object(entries) if rows.is_none() && entries.values().all(Value::is_object) => {
+                        rows = Some(
+                            entries
+                                .iter()
+                                .map(|(id, entry)| {
+                                    let mut row = Map::from_iter([("id".to_string(), Value::String(id.clone()))]);
+                                    row.extend(to_row(entry));
+                                    row
+                                })
+                                .collect(),
+                        );
+                    }
+                    Value::Array(items) => summary.push(format!("{}: {}", key, items.len())),
+                    Value::Object(_) => {}
+                    scalar => summary.push(format!("{}: {}", key, cell_text(scalar, false))),
+                }
+            }
+            if rows.is_none() {
+                return (None, Vec::new());
+            }
+            (rows, summary)
+        }
+        scalar => (Some(vec![to_row(scalar)]), Vec::new()),
+    }
+}
+
+/// One `path:line:col: [resolved] author: first line of body` entry, the
+/// format Vim, Emacs compilation-mode and VS Code problem matchers parse.
+/// Rows without a path are skipped; file-level threads, and threads whose
+/// code was deleted at local HEAD, point at line 1.
+fn quickfix_line(row: &Map<String, Value>) -> Option<String> {
+    let path = row.get("path")?.as_str()?;
+    let deleted = row.get("line_status").and_then(Value::as_str) == Some("deleted");
+    let line = if deleted {
+        1
+    } else {
+        ["current_line", "line", "original_line"]
+            .iter()
+            .find_map(|f| row.get(*f).and_then(Value::as_u64))
+            .unwrap_or(1)
+    };
+    let resolved = RESOLVED_FIELDS.iter().any(|f| row.get(*f) == Some(&Value::Bool(true)));
+    let author = row.get("author").map(|a| cell_text(a, false)).unwrap_or_default();
+    let body = row.get("body").map(|b| cell_text(b, false)).unwrap_or_default();
+
+    Some(format!(
+        "{}:{}:1: {}{}: {}{}",
+        path,
+        line,
+        if resolved { "[resolved] " } else { "" },
+        if author.is_empty() { "ghost" } else { &author },
+        if deleted { "(outdated: code removed) " } else { "" },
+        body
+    ))
+}
+
+/// A single object shown as `field`/`value` rows
+fn key_value_rows(value: &Value) -> Vec<Map<String, Value>> {
+    let Value::Object(map) = value else {
+        return Vec::new();
+    };
+    map.iter()
+        .map(|(key, field)| {
+            Map::from_iter([
+                ("field".to_string(), Value::String(key.clone())),
+                ("value".to_string(), field.clone()),
+            ])
+        })
+        .collect()
+}
+
+/// Headers and cell text for the rows, with a leading marker column for
+/// resolved threads
+fn table_cells(
+    rows: &[Map<String, Value>],
+    markdown: bool,
+    requested: Option<&[String]>,
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let mut keys: Vec<&str> = Vec::new();
+    for row in rows {
+        for key in row.keys() {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+    }
+
+    let preferred: Vec<&str> = PREFERRED_COLUMNS.iter().copied().filter(|c| keys.contains(c)).collect();
+    let columns = match requested {
+        Some(requested) => requested.iter().map(|c| c.as_str()).collect(),
+        None if preferred.is_empty() => keys.clone(),
+        None => preferred,
+    };
+    let marker = RESOLVED_FIELDS.iter().any(|f| keys.contains(f));
+
+    let mut headers: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+    if marker {
+        headers.insert(0, String::new());
+    }
+
+    let cells = rows
+        .iter()
+        .map(|row| {
+            let mut cells: Vec<String> = columns
+                .iter()
+                .map(|c| row.get(*c).map(|v| cell_text(v, markdown)).unwrap_or_default())
+                .collect();
+            if marker {
+                let resolved = RESOLVED_FIELDS.iter().any(|f| row.get(*f) == Some(&Value::Bool(true)));
+                cells.insert(0, if resolved { "✓".to_string() } else { String::new() });
+            }
+            cells
+        })
+        .collect();
+    (headers, cells)
+}
+
+/// Text of one cell. Tables show the first line of multi-line text;
+/// markdown keeps every line joined with `<br>`.
+fn cell_text(value: &Value, markdown: bool) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) if markdown => s.replace('|', "\\|").lines().collect::<Vec<_>>().join("<br>"),
+        Value::String(s) => s.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim().to_string(),
+        // Objects such as `author: { login }` show their login
+        Value::Object(map) if map.contains_key("login") => cell_text(&map["login"], markdown),
+        other => other.to_string(),
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn render_table(headers: &[String], rows: &[Vec<String>], width: usize) -> String {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (w, cell) in widths.iter_mut().zip(row) {
+            *w = (*w).max(cell.chars().count());
+        }
+    }
+
+    // Shrink the widest column until the table fits
+    let gaps = 2 * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + gaps > width {
+        let Some((widest, w)) = widths.iter().enumerate().max_by_key(|(_, w)| **w) else {
+            break;
+        };
+        if *w <= MIN_COLUMN_WIDTH {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(headers).chain(rows.iter().map(|r| r.as_slice())) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, w)| format!("{:<w$}", truncate(cell, *w), w = *w))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn render_markdown(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut out = format!("| {} |\n", headers.join(" | "));
+    out.push_str(&format!("|{}\n", "---|".repeat(headers.len())));
+    for row in rows {
+        out.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn threads() -> Value {
+        json!({
+            "total": 2,
+            "items": [
+                {"id": "PRRT_1", "path": "src/main.rs", "line": 10, "body": "Please rename this\nand that", "is_resolved": true, "author": "reviewer"},
+                {"id": "PRRT_2", "path": "src/lib.rs", "line": null, "body": "Use | pipes", "is_resolved": false, "author": "bot"},
+            ]
+        })
+    }
+
+    #[test]
+    fn test_table_marks_resolved_and_fits_width() {
+        let out = render(&threads(), OutputFormat::Table, 200, None).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "   ID      PATH         LINE  AUTHOR    BODY");
+        assert_eq!(lines[1], "✓  PRRT_1  src/main.rs  10    reviewer  Please rename this");
+        assert!(lines[2].starts_with("   PRRT_2"));
+        assert_eq!(lines[4], "total: 2");
+
+        let narrow = render(&threads(), OutputFormat::Table, 50, None).unwrap();
+        assert!(narrow.lines().all(|l| l.chars().count() <= 50));
+        assert!(narrow.contains('…'));
+    }
+
+    #[test]
+    fn test_markdown_escapes_cells() {
+        let out = render(&threads(), OutputFormat::Markdown, 80, None).unwrap();
+        assert!(out.starts_with("|  | ID | PATH | LINE | AUTHOR | BODY |\n|---|---|---|---|---|---|\n"));
+        assert!(out.contains("Please rename this<br>and that"));
+        assert!(out.contains("Use \\| pipes"));
+    }
+
+    #[test]
+    fn test_ndjson_and_plain() {
+        let out = render(&threads(), OutputFormat::Ndjson, 80, None).unwrap();
+        assert_eq!(out.lines().count(), 2);
+        assert!(out.lines().all(|l| serde_json::from_str::<Value>(l).is_ok()));
+
+        let plain = render(&threads(), OutputFormat::Plain, 80, None).unwrap();
+        assert_eq!(plain.lines().next(), Some("✓\tPRRT_1\tsrc/main.rs\t10\treviewer\tPlease rename this"));
+    }
+
+    #[test]
+    fn test_draft_map_and_single_object() {
+        let drafts = json!({"pr_number": 1, "total": 1, "drafts": {"PRRT_1": {"body": "Done", "resolve": true}}});
+        let out = render(&drafts, OutputFormat::Plain, 80, None).unwrap();
+        assert_eq!(out, "PRRT_1\tDone\ttrue\n");
+
+        let comment = json!({"id": "PRRC_1", "url": "https://example.com"});
+        let out = render(&comment, OutputFormat::Plain, 80, None).unwrap();
+        assert_eq!(out, "id\tPRRC_1\nurl\thttps://example.com\n");
+    }
+
+    #[test]
+    fn test_project_fields() {
+        let fields = vec!["path".to_string(), "id".to_string()];
+        let projected = project(&threads(), &fields).unwrap();
+        assert_eq!(
+            projected,
+            json!([{"id": "PRRT_1", "path": "src/main.rs"}, {"id": "PRRT_2", "path": "src/lib.rs"}])
+        );
+        let out = render(&projected, OutputFormat::Plain, 80, Some(&fields)).unwrap();
+        assert_eq!(out.lines().next(), Some("src/main.rs\tPRRT_1"));
+
+        let comment = json!({"id": "PRRC_1", "url": "https://example.com"});
+        assert_eq!(project(&comment, &["url".to_string()]).unwrap(), json!({"url": "https://example.com"}));
+
+        let err = project(&threads(), &["nope".to_string()]).unwrap_err();
+        assert_eq!((err.kind(), err.exit_code()), ("invalid_input", 2));
+        let err = err.to_string();
+        assert!(err.contains("Unknown JSON field: \"nope\""));
+        assert!(err.contains("\n  author\n"));
+    }
+
+    #[test]
+    fn test_quickfix_lines() {
+        let out = render(&threads(), OutputFormat::Quickfix, 80, None).unwrap();
+        assert_eq!(
+            out,
+            "src/main.rs:10:1: [resolved] reviewer: Please rename this\nsrc/lib.rs:1:1: bot: Use | pipes\n"
+        );
+
+        let moved = json!({"path": "src/a.rs", "line": 3, "current_line": 5, "original_line": 2, "body": "x", "author": null});
+        assert_eq!(render(&moved, OutputFormat::Quickfix, 80, None).unwrap(), "src/a.rs:5:1: ghost: x\n");
+
+        // The stale line of deleted code would point at unrelated code
+        let deleted = json!({"path": "src/a.rs", "line": 3, "line_status": "deleted", "body": "x", "author": "reviewer"});
+        assert_eq!(
+            render(&deleted, OutputFormat::Quickfix, 80, None).unwrap(),
+            "src/a.rs:1:1: reviewer: (outdated: code removed) x\n"
+        );
+        assert_eq!(render(&json!({"total": 0}), OutputFormat::Quickfix, 80, None).unwrap(), "");
+    }
+}
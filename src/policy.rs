@@ -0,0 +1,209 @@
+use crate::error::{GhReplyError, Result};
+use std::fmt;
+use std::io::{BufRead, IsTerminal, Write};
+
+/// Environment variable controlling whether threads may be resolved
+pub const RESOLVE_ENV: &str = "GHREPLY_RESOLVE";
+
+/// Policy applied whenever a reply asks for its thread to be resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResolvePolicy {
+    /// Resolve whenever requested (default)
+    #[default]
+    Allow,
+    /// Never resolve threads
+    Never,
+    /// Ask for confirmation on the terminal before each resolve
+    AlwaysAsk,
+    /// Only resolve threads started by the authenticated user
+    OnlyOwnThreads,
+    /// Only resolve threads started by bots (e.g. gemini-code-assist, Copilot)
+    OnlyBotThreads,
+}
+
+/// Author of the first comment in a review thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadAuthor {
+    pub login: String,
+    pub is_bot: bool,
+}
+
+/// Outcome of applying the resolve policy to a thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveDecision {
+    Resolve,
+    Skip(String),
+}
+
+impl ResolvePolicy {
+    /// Read the policy from `GHREPLY_RESOLVE`, defaulting to `Allow` when unset
+    pub fn from_env() -> Result<Self> {
+        match std::env::var(RESOLVE_ENV) {
+            Ok(value) => Self::parse(&value),
+            Err(_) => Ok(Self::Allow),
+        }
+    }
+
+    /// Parse a policy value. `false` is kept as an alias of `never` for
+    /// compatibility with the Node.js implementation.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "true" | "always" | "allow" => Ok(Self::Allow),
+            "false" | "never" => Ok(Self::Never),
+            "always-ask" | "ask" => Ok(Self::AlwaysAsk),
+            "only-own-threads" => Ok(Self::OnlyOwnThreads),
+            "only-bot-threads" => Ok(Self::OnlyBotThreads),
+            other => Err(GhReplyError::ConfigError(format!(
+                "Invalid {} value '{}'. Expected one of: true, never, always-ask, only-own-threads, only-bot-threads",
+                RESOLVE_ENV, other
+            ))),
+        }
+    }
+
+    /// Whether the thread author must be fetched to evaluate this policy
+    pub fn needs_author(&self) -> bool {
+        matches!(self, Self::OnlyOwnThreads | Self::OnlyBotThreads)
+    }
+
+    /// Decide whether a thread may be resolved.
+    /// `viewer` is the authenticated user's login.
+    pub fn check(
+        &self,
+        thread_id: &str,
+        author: Option<&ThreadAuthor>,
+        viewer: &str,
+    ) -> ResolveDecision {
+        match self {
+            Self::Allow => ResolveDecision::Resolve,
+            Self::Never => ResolveDecision::Skip(format!("resolving is disabled by {}", self.describe())),
+            Self::AlwaysAsk => {
+                if confirm(&format!("Resolve thread {}?", thread_id)) {
+                    ResolveDecision::Resolve
+                } else {
+                    ResolveDecision::Skip("not confirmed".to_string())
+                }
+            }
+            Self::OnlyOwnThreads => match author {
+                Some(a) if !viewer.is_empty() && a.login == viewer => ResolveDecision::Resolve,
+                Some(a) => ResolveDecision::Skip(format!(
+                    "thread was started by {}, not {} ({})",
+                    a.login,
+                    if viewer.is_empty() { "the authenticated user" } else { viewer },
+                    self.describe()
+                )),
+                None => ResolveDecision::Skip(format!("thread author unknown ({})", self.describe())),
+            },
+            Self::OnlyBotThreads => match author {
+                Some(a) if a.is_bot => ResolveDecision::Resolve,
+                Some(a) => ResolveDecision::Skip(format!(
+                    "thread was started by {}, not a bot ({})",
+                    a.login,
+                    self.describe()
+                )),
+                None => ResolveDecision::Skip(format!("thread author unknown ({})", self.describe())),
+            },
+        }
+    }
+
+    fn describe(&self) -> String {
+        format!("{}={}", RESOLVE_ENV, self)
+    }
+}
+
+impl fmt::Display for ResolvePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Allow => "true",
+            Self::Never => "never",
+            Self::AlwaysAsk => "always-ask",
+            Self::OnlyOwnThreads => "only-own-threads",
+            Self::OnlyBotThreads => "only-bot-threads",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Ask a yes/no question on stderr. Non-interactive sessions answer no.
+fn confirm(question: &str) -> bool {
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        eprintln!("{} [y/N] (no terminal, answering no)", question);
+        return false;
+    }
+
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush().ok();
+
+    let mut answer = String::new();
+    if stdin.lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn author(login: &str, is_bot: bool) -> ThreadAuthor {
+        ThreadAuthor {
+            login: login.to_string(),
+            is_bot,
+        }
+    }
+
+    #[test]
+    fn test_parse_policy_values() {
+        assert_eq!(ResolvePolicy::parse("").unwrap(), ResolvePolicy::Allow);
+        assert_eq!(ResolvePolicy::parse("true").unwrap(), ResolvePolicy::Allow);
+        assert_eq!(ResolvePolicy::parse("false").unwrap(), ResolvePolicy::Never);
+        assert_eq!(ResolvePolicy::parse("never").unwrap(), ResolvePolicy::Never);
+        assert_eq!(ResolvePolicy::parse("always-ask").unwrap(), ResolvePolicy::AlwaysAsk);
+        assert_eq!(ResolvePolicy::parse("only-own-threads").unwrap(), ResolvePolicy::OnlyOwnThreads);
+        assert_eq!(ResolvePolicy::parse("Only-Bot-Threads").unwrap(), ResolvePolicy::OnlyBotThreads);
+    }
+
+    #[test]
+    fn test_parse_invalid_policy() {
+        let result = ResolvePolicy::parse("sometimes");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("GHREPLY_RESOLVE"));
+    }
+
+    #[test]
+    fn test_allow_and_never() {
+        assert_eq!(ResolvePolicy::Allow.check("t", None, "me"), ResolveDecision::Resolve);
+        assert!(matches!(
+            ResolvePolicy::Never.check("t", None, "me"),
+            ResolveDecision::Skip(_)
+        ));
+    }
+
+    #[test]
+    fn test_only_own_threads() {
+        let policy = ResolvePolicy::OnlyOwnThreads;
+        assert_eq!(policy.check("t", Some(&author("me", false)), "me"), ResolveDecision::Resolve);
+        assert!(matches!(
+            policy.check("t", Some(&author("someone", false)), "me"),
+            ResolveDecision::Skip(_)
+        ));
+        assert!(matches!(policy.check("t", None, "me"), ResolveDecision::Skip(_)));
+        assert!(matches!(
+            policy.check("t", Some(&author("", false)), ""),
+            ResolveDecision::Skip(_)
+        ));
+    }
+
+    #[test]
+    fn test_only_bot_threads() {
+        let policy = ResolvePolicy::OnlyBotThreads;
+        assert_eq!(
+            policy.check("t", Some(&author("gemini-code-assist", true)), "me"),
+            ResolveDecision::Resolve
+        );
+        assert!(matches!(
+            policy.check("t", Some(&author("me", false)), "me"),
+            ResolveDecision::Skip(_)
+        ));
+    }
+}
@@ -0,0 +1,148 @@
+/// A numbered line of code shown around a review thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnippetLine {
+    /// Line number on the commented side (None for lines only on the other side)
+    pub number: Option<u32>,
+    /// Diff marker: ' ', '+' or '-'
+    pub marker: char,
+    pub text: String,
+    /// Part of the commented range
+    pub highlighted: bool,
+}
+
+/// Lines of a comment's `diffHunk` within `context` lines of `start..=end`.
+/// Numbers follow the commented side (`LEFT` is the base, otherwise the head).
+pub fn from_diff_hunk(hunk: &str, side: Option<&str>, start: u32, end: u32, context: u32) -> Vec<SnippetLine> {
+    let left = side == Some("LEFT");
+    let mut lines = hunk.lines();
+    let Some(header) = lines.next().and_then(|h| crate::git::parse_hunks(h).into_iter().next()) else {
+        return Vec::new();
+    };
+
+    let mut old_line = header.old_start;
+    let mut new_line = header.new_start;
+    let mut result = Vec::new();
+
+    for line in lines {
+        let marker = line.chars().next().unwrap_or(' ');
+        let text = line.get(1..).unwrap_or("").to_string();
+        let (old, new) = match marker {
+            '+' => (None, Some(new_line)),
+            '-' => (Some(old_line), None),
+            _ => (Some(old_line), Some(new_line)),
+        };
+        if old.is_some() {
+            old_line += 1;
+        }
+        if new.is_some() {
+            new_line += 1;
+        }
+
+        let number = if left { old } else { new };
+        result.push(SnippetLine {
+            number,
+            marker: if marker == '+' || marker == '-' { marker } else { ' ' },
+            text,
+            highlighted: number.is_some_and(|n| n >= start && n <= end),
+        });
+    }
+
+    // Keep lines of the other side only when they sit inside the window
+    let first = start.saturating_sub(context);
+    let last = end + context;
+    let in_window = |n: u32| n >= first && n <= last;
+    let Some(begin) = result.iter().position(|l| l.number.is_some_and(in_window)) else {
+        return Vec::new();
+    };
+    let finish = result.iter().rposition(|l| l.number.is_some_and(in_window)).unwrap_or(begin);
+    result.truncate(finish + 1);
+    result.drain(..begin);
+    result
+}
+
+/// Lines of a file within `context` lines of `start..=end`
+pub fn from_file(text: &str, start: u32, end: u32, context: u32) -> Vec<SnippetLine> {
+    let first = start.saturating_sub(context).max(1);
+    let last = end + context;
+    crate::git::line_range(text, first, last)
+        .into_iter()
+        .zip(first..)
+        .map(|(line, number)| SnippetLine {
+            number: Some(number),
+            marker: ' ',
+            text: line.to_string(),
+            highlighted: number >= start && number <= end,
+        })
+        .collect()
+}
+
+/// Render snippet lines with a line number gutter; commented lines are marked
+/// with `>` and, when `color` is set, shown in bold
+pub fn render(lines: &[SnippetLine], color: bool) -> String {
+    let width = lines
+        .iter()
+        .filter_map(|l| l.number)
+        .max()
+        .unwrap_or(0)
+        .to_string()
+        .len();
+
+    let mut out = String::new();
+    for line in lines {
+        let number = line.number.map(|n| n.to_string()).unwrap_or_default();
+        let pointer = if line.highlighted { '>' } else { ' ' };
+        let row = format!("{} {:>width$} |{}{}", pointer, number, line.marker, line.text, width = width);
+        if color && line.highlighted {
+            out.push_str(&format!("\x1b[1m{}\x1b[0m\n", row));
+        } else {
+            out.push_str(&row);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HUNK: &str = "@@ -8,5 +8,6 @@ fn main() {
+ let a = 1;
+ let b = 2;
+-let c = 3;
++let c = 30;
++let d = 4;
+ println!(\"{}\", a);";
+
+    #[test]
+    fn test_from_diff_hunk_right_side() {
+        let lines = from_diff_hunk(HUNK, Some("RIGHT"), 10, 11, 1);
+        let numbers: Vec<_> = lines.iter().map(|l| l.number).collect();
+        assert_eq!(numbers, vec![Some(9), None, Some(10), Some(11), Some(12)]);
+        let highlighted: Vec<_> = lines.iter().filter(|l| l.highlighted).map(|l| l.text.as_str()).collect();
+        assert_eq!(highlighted, vec!["let c = 30;", "let d = 4;"]);
+    }
+
+    #[test]
+    fn test_from_diff_hunk_left_side() {
+        let lines = from_diff_hunk(HUNK, Some("LEFT"), 10, 10, 0);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].marker, '-');
+        assert!(lines[0].highlighted);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let text = "a\nb\nc\nd\ne\n";
+        let lines = from_file(text, 2, 2, 1);
+        let texts: Vec<_> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["a", "b", "c"]);
+        assert!(lines[1].highlighted);
+    }
+
+    #[test]
+    fn test_render() {
+        let lines = from_file("a\nb\n", 2, 2, 1);
+        assert_eq!(render(&lines, false), "  1 | a\n> 2 | b\n");
+    }
+}
@@ -4,33 +4,57 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct DraftStore {
     drafts: Drafts,
+    /// File the store was loaded from; `save` writes back to it
+    path: Option<PathBuf>,
 }
 
 impl DraftStore {
     const STORE_PATH: &'static str = ".git/info/gh-reply-drafts.json";
 
-    pub fn load() -> Result<Self> {
-        let path = Self::store_path()?;
+    /// Key prefix for drafts that start a new review thread
+    pub const NEW_THREAD_PREFIX: &'static str = "new:";
+
+    /// Draft key for a new review thread at `path:line` on `side`, so
+    /// comments on both sides of the same line are kept apart
+    pub fn new_thread_key(path: &str, line: u32, side: &str) -> String {
+        format!("{}{}:{}:{}", Self::NEW_THREAD_PREFIX, path, line, side)
+    }
+
+    /// Whether a draft key refers to a new review thread rather than a reply
+    pub fn is_new_thread_key(key: &str) -> bool {
+        key.starts_with(Self::NEW_THREAD_PREFIX)
+    }
+
+    pub async fn load() -> Result<Self> {
+        let path = Self::store_path().await?;
         if !path.exists() {
-            return Ok(Self::default());
+            return Ok(Self {
+                path: Some(path),
+                ..Self::default()
+            });
         }
 
         let content = fs::read_to_string(&path)?;
         let drafts: Drafts = serde_json::from_str(&content)?;
-        Ok(Self { drafts })
+        Ok(Self {
+            drafts,
+            path: Some(path),
+        })
     }
 
     pub fn save(&self) -> Result<()> {
-        let path = Self::store_path()?;
+        let path = self.path.as_ref().ok_or_else(|| {
+            crate::error::GhReplyError::StoreError("Draft store was not loaded from a repository".to_string())
+        })?;
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
         let content = serde_json::to_string_pretty(&self.drafts)?;
-        fs::write(&path, content)?;
+        fs::write(path, content)?;
         Ok(())
     }
 
@@ -38,7 +62,7 @@ impl DraftStore {
         let pr_key = pr_number.to_string();
         self.drafts
             .entry(pr_key)
-            .or_insert_with(HashMap::new)
+            .or_default()
             .insert(thread_id.to_string(), draft);
     }
 
@@ -67,10 +91,11 @@ impl DraftStore {
         self.drafts.remove(&pr_key);
     }
 
-    fn store_path() -> Result<PathBuf> {
-        let output = std::process::Command::new("git")
-            .args(&["rev-parse", "--show-toplevel"])
-            .output()?;
+    async fn store_path() -> Result<PathBuf> {
+        let output = tokio::process::Command::new("git")
+            .args(["rev-parse", "--show-toplevel"])
+            .output()
+            .await?;
 
         if !output.status.success() {
             return Err(crate::error::GhReplyError::StoreError(
@@ -82,14 +107,6 @@ impl DraftStore {
     }
 }
 
-impl Default for DraftStore {
-    fn default() -> Self {
-        Self {
-            drafts: HashMap::new(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,8 +119,11 @@ mod tests {
             body: "Test message".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: Some(true),
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -114,6 +134,36 @@ mod tests {
         assert_eq!(retrieved.unwrap().body, "Test message");
     }
 
+    #[test]
+    fn test_new_thread_draft() {
+        let mut store = DraftStore::default();
+
+        let draft = DraftEntry {
+            body: "This changed because...".to_string(),
+            path: Some("src/x.rs".to_string()),
+            line: Some(42),
+            start_line: Some(40),
+            side: Some("RIGHT".to_string()),
+            original_comment: None,
+            resolve: None,
+            rendered: None,
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+        };
+
+        let key = DraftStore::new_thread_key("src/x.rs", 42, "RIGHT");
+        assert_eq!(key, "new:src/x.rs:42:RIGHT");
+        assert_ne!(key, DraftStore::new_thread_key("src/x.rs", 42, "LEFT"));
+        assert!(DraftStore::is_new_thread_key(&key));
+        assert!(!DraftStore::is_new_thread_key("PRRT_kwDOQZVIxM5nXEc_"));
+
+        store.add_draft(123, &key, draft);
+
+        let target = store.get_draft(123, &key).unwrap().new_thread_target().unwrap();
+        assert_eq!(target.path, "src/x.rs");
+        assert_eq!(target.line, 42);
+        assert_eq!(target.start_line, Some(40));
+    }
+
     #[test]
     fn test_get_nonexistent_draft() {
         let store = DraftStore::default();
@@ -130,8 +180,11 @@ mod tests {
             body: "Message 1".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: None,
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -139,8 +192,11 @@ mod tests {
             body: "Message 2".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: None,
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -161,8 +217,11 @@ mod tests {
             body: "Test message".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: None,
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -185,8 +244,11 @@ mod tests {
             body: "Message 1".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: None,
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -194,8 +256,11 @@ mod tests {
             body: "Message 2".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: None,
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -217,8 +282,11 @@ mod tests {
             body: "PR 123".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: None,
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -226,8 +294,11 @@ mod tests {
             body: "PR 456".to_string(),
             path: None,
             line: None,
+            start_line: None,
+            side: None,
             original_comment: None,
             resolve: None,
+            rendered: None,
             timestamp: "2024-01-01T00:00:00Z".to_string(),
         };
 
@@ -0,0 +1,134 @@
+/// Build a reply body containing a GitHub ```suggestion``` block that
+/// replaces the commented lines with `lines`, optionally preceded by `message`
+pub fn format_suggestion(message: Option<&str>, lines: &[&str]) -> String {
+    // Use a longer fence when the suggested code itself contains backticks
+    let longest_run = lines
+        .iter()
+        .flat_map(|line| line.split(|c| c != '`'))
+        .map(|run| run.len())
+        .max()
+        .unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+
+    let mut body = String::new();
+    if let Some(message) = message.filter(|m| !m.trim().is_empty()) {
+        body.push_str(message.trim_end());
+        body.push_str("\n\n");
+    }
+    body.push_str(&fence);
+    body.push_str("suggestion\n");
+    for line in lines {
+        body.push_str(line);
+        body.push('\n');
+    }
+    body.push_str(&fence);
+    body
+}
+
+/// Extract the contents of every ```suggestion``` block in a comment body
+pub fn parse_suggestions(body: &str) -> Vec<Vec<String>> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+
+    for line in body.lines() {
+        let trimmed = line.trim();
+        match &mut current {
+            None => {
+                let fence_len = trimmed.chars().take_while(|c| *c == '`').count();
+                if fence_len >= 3 && trimmed[fence_len..].trim() == "suggestion" {
+                    current = Some(("`".repeat(fence_len), Vec::new()));
+                }
+            }
+            Some((fence, lines)) => {
+                if trimmed == fence.as_str() {
+                    blocks.push(std::mem::take(lines));
+                    current = None;
+                } else {
+                    lines.push(line.to_string());
+                }
+            }
+        }
+    }
+
+    blocks
+}
+
+/// Replace lines `start..=end` (1-based) of `text` with `replacement`,
+/// keeping the file's line endings and trailing newline
+pub fn replace_lines(text: &str, start: u32, end: u32, replacement: &[String]) -> String {
+    let newline = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<&str> = text.lines().collect();
+    let start = (start.max(1) as usize - 1).min(lines.len());
+    let end = (end as usize).clamp(start, lines.len());
+    lines.splice(start..end, replacement.iter().map(|l| l.as_str()));
+
+    let mut result = lines.join(newline);
+    if text.ends_with('\n') && !result.is_empty() {
+        result.push_str(newline);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suggestions() {
+        let body = "Consider a constant here.\n\n```suggestion\nconst MAX: u32 = 3;\nlet x = MAX;\n```\n\nThanks!";
+        let blocks = parse_suggestions(body);
+        assert_eq!(blocks, vec![vec!["const MAX: u32 = 3;".to_string(), "let x = MAX;".to_string()]]);
+    }
+
+    #[test]
+    fn test_parse_suggestions_ignores_other_blocks() {
+        let body = "```rust\nfoo();\n```\n````suggestion\n/// ```\n````";
+        let blocks = parse_suggestions(body);
+        assert_eq!(blocks, vec![vec!["/// ```".to_string()]]);
+    }
+
+    #[test]
+    fn test_parse_suggestions_roundtrip() {
+        let body = format_suggestion(Some("Done"), &["a", "b"]);
+        assert_eq!(parse_suggestions(&body), vec![vec!["a".to_string(), "b".to_string()]]);
+    }
+
+    #[test]
+    fn test_replace_lines() {
+        let text = "one\ntwo\nthree\n";
+        assert_eq!(replace_lines(text, 2, 2, &["TWO".to_string()]), "one\nTWO\nthree\n");
+        assert_eq!(replace_lines(text, 2, 3, &[]), "one\n");
+        assert_eq!(
+            replace_lines("a\r\nb", 1, 1, &["x".to_string(), "y".to_string()]),
+            "x\r\ny\r\nb"
+        );
+    }
+
+    #[test]
+    fn test_format_suggestion() {
+        let body = format_suggestion(Some("Updated as requested."), &["let x = 1;", "let y = 2;"]);
+        assert_eq!(
+            body,
+            "Updated as requested.\n\n```suggestion\nlet x = 1;\nlet y = 2;\n```"
+        );
+    }
+
+    #[test]
+    fn test_format_suggestion_without_message() {
+        let body = format_suggestion(None, &["foo();"]);
+        assert_eq!(body, "```suggestion\nfoo();\n```");
+    }
+
+    #[test]
+    fn test_format_suggestion_deletes_lines() {
+        let body = format_suggestion(None, &[]);
+        assert_eq!(body, "```suggestion\n```");
+    }
+
+    #[test]
+    fn test_format_suggestion_with_backticks() {
+        let body = format_suggestion(None, &["/// ```rust", "/// ```"]);
+        assert!(body.starts_with("````suggestion\n"));
+        assert!(body.ends_with("\n````"));
+    }
+}
@@ -0,0 +1,103 @@
+/// Trailer naming a review thread a commit addresses (`3` or `PRRT_xxx`)
+pub const THREAD_TRAILER: &str = "Addresses-thread";
+/// Trailer with the reply for the preceding `Addresses-thread` trailers
+pub const REPLY_TRAILER: &str = "Reply";
+
+/// A review thread referenced from a commit message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThreadRef {
+    /// Thread ID or 1-based index, as written in the trailer
+    pub thread: String,
+    pub reply: Option<String>,
+}
+
+/// `Key: value` trailers from the last paragraph of a commit message.
+/// Lines starting with whitespace continue the previous trailer.
+pub fn parse_trailers(message: &str) -> Vec<(String, String)> {
+    let message = message.trim_end();
+    let paragraph = message.rsplit("\n\n").next().unwrap_or("");
+    // A message consisting of a single paragraph is only a subject
+    if paragraph.len() == message.len() {
+        return Vec::new();
+    }
+
+    let mut trailers: Vec<(String, String)> = Vec::new();
+    for line in paragraph.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = trailers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            continue;
+        }
+        trailers.push((key.to_string(), value.trim().to_string()));
+    }
+    trailers
+}
+
+/// Threads referenced by `Addresses-thread` trailers. A `Reply` trailer
+/// applies to the threads listed before it that have no reply yet.
+pub fn thread_refs(message: &str) -> Vec<ThreadRef> {
+    let mut refs: Vec<ThreadRef> = Vec::new();
+    let mut pending = 0;
+
+    for (key, value) in parse_trailers(message) {
+        if key.eq_ignore_ascii_case(THREAD_TRAILER) {
+            refs.extend(value.split([',', ' ']).filter(|t| !t.is_empty()).map(|t| ThreadRef {
+                thread: t.trim_start_matches('#').to_string(),
+                reply: None,
+            }));
+            pending = refs.iter().filter(|r| r.reply.is_none()).count();
+        } else if key.eq_ignore_ascii_case(REPLY_TRAILER) && pending > 0 {
+            let start = refs.len() - pending;
+            for r in &mut refs[start..] {
+                r.reply = Some(value.clone());
+            }
+            pending = 0;
+        }
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trailers() {
+        let message = "Fix overflow\n\nUse checked math.\n\nAddresses-thread: 3\nSigned-off-by: A <a@b>\n";
+        let trailers = parse_trailers(message);
+        assert_eq!(
+            trailers,
+            vec![
+                ("Addresses-thread".to_string(), "3".to_string()),
+                ("Signed-off-by".to_string(), "A <a@b>".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subject_only_has_no_trailers() {
+        assert!(parse_trailers("Addresses-thread: 3").is_empty());
+    }
+
+    #[test]
+    fn test_thread_refs_with_reply() {
+        let message = "Rename helper\n\nAddresses-thread: 1, PRRT_abc\nReply: Renamed in\n  {{short_commit}}\nAddresses-thread: #4\n";
+        let refs = thread_refs(message);
+        assert_eq!(
+            refs,
+            vec![
+                ThreadRef { thread: "1".to_string(), reply: Some("Renamed in {{short_commit}}".to_string()) },
+                ThreadRef { thread: "PRRT_abc".to_string(), reply: Some("Renamed in {{short_commit}}".to_string()) },
+                ThreadRef { thread: "4".to_string(), reply: None },
+            ]
+        );
+    }
+}
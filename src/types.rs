@@ -33,13 +33,39 @@ pub struct DraftEntry {
     pub path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub line: Option<u32>,
+    /// First line of a multi-line range (new thread drafts only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+    /// Diff side, LEFT or RIGHT (new thread drafts only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub original_comment: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resolve: Option<bool>,
+    /// Body is already expanded; `draft send` posts it without templating
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rendered: Option<bool>,
     pub timestamp: String,
 }
 
+impl DraftEntry {
+    /// An empty draft marked for resolve: resolve the thread without replying
+    pub fn is_resolve_only(&self) -> bool {
+        self.body.trim().is_empty() && self.resolve.unwrap_or(false)
+    }
+
+    /// Location of a new review thread, if this draft starts one
+    pub fn new_thread_target(&self) -> Option<NewThreadTarget> {
+        Some(NewThreadTarget {
+            path: self.path.clone()?,
+            line: self.line?,
+            start_line: self.start_line,
+            side: self.side.clone(),
+        })
+    }
+}
+
 /// Drafts storage: PR number -> thread ID -> DraftEntry
 pub type Drafts = HashMap<String, HashMap<String, DraftEntry>>;
 
@@ -49,7 +75,94 @@ pub struct ReviewThread {
     pub id: String,
     pub path: String,
     pub line: Option<u32>,
+    /// First line of a multi-line comment range
+    pub start_line: Option<u32>,
     pub diff_side: Option<String>,
     pub body: String,
     pub is_resolved: bool,
+    /// The diff the thread was made on no longer matches the PR head
+    #[serde(default)]
+    pub is_outdated: bool,
+    /// Line in the diff at the time of the original comment
+    #[serde(default)]
+    pub original_line: Option<u32>,
+    /// First line of a multi-line comment at `original_commit_oid`
+    #[serde(default)]
+    pub original_start_line: Option<u32>,
+    /// LINE for line comments, FILE for file-level comments
+    #[serde(default)]
+    pub subject_type: Option<String>,
+    /// Login of the author who started the thread
+    pub author: Option<String>,
+    /// Login of the author of the latest comment in the thread
+    pub last_author: Option<String>,
+    /// Commit the thread's line numbers refer to
+    pub commit_oid: Option<String>,
+    /// Commit `original_line` refers to
+    #[serde(default)]
+    pub original_commit_oid: Option<String>,
+    /// Line at local HEAD corresponding to `line`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_line: Option<u32>,
+    /// How the commented lines changed between `commit_oid` and local HEAD
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_status: Option<LineStatus>,
+}
+
+/// How a thread's commented lines changed since the review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineStatus {
+    /// Same content at the same line
+    Unchanged,
+    /// Same content, shifted by edits elsewhere in the file
+    Moved,
+    /// The commented lines were modified
+    Rewritten,
+    /// The commented lines were removed
+    Deleted,
+}
+
+impl ReviewThread {
+    /// Commented line range (start..=end), if the thread is anchored to lines
+    pub fn line_range(&self) -> Option<(u32, u32)> {
+        let end = self.line?;
+        Some((self.start_line.unwrap_or(end), end))
+    }
+
+    /// Commented line range at the commit the comment was made on; the
+    /// numbering used by the comment's diff hunk
+    pub fn original_line_range(&self) -> Option<(u32, u32)> {
+        let end = self.original_line?;
+        Some((self.original_start_line.unwrap_or(end), end))
+    }
+}
+
+/// Diff location for a new review thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewThreadTarget {
+    pub path: String,
+    pub line: u32,
+    pub start_line: Option<u32>,
+    pub side: Option<String>,
+}
+
+/// Metadata of a comment created by gh-reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostedComment {
+    pub thread_id: String,
+    pub id: String,
+    pub database_id: Option<u64>,
+    pub url: String,
+    pub created_at: String,
+}
+
+/// A comment in a review thread's conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadComment {
+    pub author: Option<String>,
+    pub body: String,
+    pub created_at: String,
 }
@@ -1,6 +1,7 @@
 use crate::error::{GhReplyError, Result};
 use crate::types::ReplyContext;
 use regex::Regex;
+use serde_json::Value;
 use std::collections::HashMap;
 
 pub struct TemplateExpander {
@@ -28,6 +29,10 @@ impl TemplateExpander {
         vars.insert("base_branch".to_string(), context.base.base_branch.clone());
         vars.insert("head_branch".to_string(), context.base.head_branch.clone());
         vars.insert("local_commit".to_string(), context.base.local_commit.clone());
+        vars.insert(
+            "short_commit".to_string(),
+            context.base.local_commit.chars().take(7).collect(),
+        );
 
         Self { vars }
     }
@@ -52,6 +57,331 @@ impl TemplateExpander {
 
         Ok(result)
     }
+
+    /// Expand only the variables this expander knows, leaving any other
+    /// `{{var}}` untouched so it can be expanded later (e.g. when sending)
+    pub fn expand_known(&self, template: &str) -> Result<String> {
+        let re = Regex::new(r"\{\{\s*(\w+)\s*\}\}")
+            .map_err(|e| GhReplyError::TemplateError(format!("Invalid regex: {}", e)))?;
+
+        Ok(re
+            .replace_all(template, |caps: &regex::Captures| {
+                self.vars
+                    .get(&caps[1])
+                    .cloned()
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned())
+    }
+
+    /// Render a Go-template-like `template` over JSON `data`.
+    ///
+    /// Besides the plain `{{var}}` variables of [`expand`](Self::expand), it
+    /// supports field access (`{{.path}}`, `{{.comment.author}}`, `{{.}}`),
+    /// `{{range .items}}...{{else}}...{{end}}` (with optional
+    /// `{{range $i, $item := .items}}` variables), `{{if .x}}...{{end}}`, the
+    /// functions `len` and `truncate N`, and `{{-`/`-}}` whitespace trimming.
+    pub fn render_value(&self, template: &str, data: &Value) -> Result<String> {
+        let actions = split_actions(template)?;
+        let mut pos = 0;
+        let (nodes, stop) = parse_nodes(&actions, &mut pos)?;
+        if let Some(stop) = stop {
+            return Err(template_error(format!("unexpected {{{{{}}}}}", stop)));
+        }
+
+        let mut out = String::new();
+        let scope = Scope { root: data, vars: Vec::new() };
+        self.render_nodes(&nodes, data, &scope, &mut out)?;
+        Ok(out)
+    }
+
+    fn render_nodes(&self, nodes: &[Node], dot: &Value, scope: &Scope, out: &mut String) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Output(expr) => out.push_str(&value_text(&self.eval(expr, dot, scope)?)),
+                Node::If(expr, body, otherwise) => {
+                    let branch = if truthy(&self.eval(expr, dot, scope)?) { body } else { otherwise };
+                    self.render_nodes(branch, dot, scope, out)?;
+                }
+                Node::Range { vars, expr, body, otherwise } => {
+                    let items: Vec<(Value, Value)> = match self.eval(expr, dot, scope)? {
+                        Value::Array(items) => items.into_iter().enumerate().map(|(i, v)| (Value::from(i), v)).collect(),
+                        Value::Object(map) => map.into_iter().map(|(k, v)| (Value::String(k), v)).collect(),
+                        Value::Null => Vec::new(),
+                        other => return Err(template_error(format!("range can't iterate over {}", other))),
+                    };
+                    if items.is_empty() {
+                        self.render_nodes(otherwise, dot, scope, out)?;
+                        continue;
+                    }
+                    for (key, item) in &items {
+                        let mut inner = Scope { root: scope.root, vars: scope.vars.clone() };
+                        match vars.as_slice() {
+                            [value] => inner.vars.push((value.clone(), item.clone())),
+                            [key_var, value] => {
+                                inner.vars.push((key_var.clone(), key.clone()));
+                                inner.vars.push((value.clone(), item.clone()));
+                            }
+                            _ => {}
+                        }
+                        self.render_nodes(body, item, &inner, out)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn eval(&self, expr: &Expr, dot: &Value, scope: &Scope) -> Result<Value> {
+        Ok(match expr {
+            Expr::Str(s) => Value::String(s.clone()),
+            Expr::Num(n) => Value::from(*n),
+            Expr::Var(name) => self.vars.get(name).map(|v| Value::String(v.clone())).unwrap_or(Value::Null),
+            Expr::Path(root, fields) => {
+                let mut value = match root {
+                    Root::Dot => dot.clone(),
+                    Root::Variable(name) if name.is_empty() => scope.root.clone(),
+                    Root::Variable(name) => scope
+                        .vars
+                        .iter()
+                        .rev()
+                        .find(|(n, _)| n == name)
+                        .map(|(_, v)| v.clone())
+                        .ok_or_else(|| template_error(format!("undefined variable ${}", name)))?,
+                };
+                for field in fields {
+                    value = value.get(field.as_str()).cloned().unwrap_or(Value::Null);
+                }
+                value
+            }
+            Expr::Call(name, args) => {
+                let args = args.iter().map(|a| self.eval(a, dot, scope)).collect::<Result<Vec<_>>>()?;
+                match (name.as_str(), args.as_slice()) {
+                    ("len", [value]) => Value::from(match value {
+                        Value::Array(items) => items.len(),
+                        Value::Object(map) => map.len(),
+                        Value::String(s) => s.chars().count(),
+                        Value::Null => 0,
+                        other => return Err(template_error(format!("len of {}", other))),
+                    }),
+                    ("truncate", [Value::Number(width), value]) => {
+                        let width = width.as_u64().unwrap_or(0) as usize;
+                        let text = value_text(value);
+                        if text.chars().count() <= width {
+                            Value::String(text)
+                        } else {
+                            let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+                            truncated.push('…');
+                            Value::String(truncated)
+                        }
+                    }
+                    _ => return Err(template_error(format!("unknown function or wrong arguments: {}", name))),
+                }
+            }
+        })
+    }
+}
+
+fn template_error(message: String) -> GhReplyError {
+    GhReplyError::TemplateError(message)
+}
+
+/// Variables bound by `range`, and the data passed to the template (`$`)
+struct Scope<'a> {
+    root: &'a Value,
+    vars: Vec<(String, Value)>,
+}
+
+#[derive(Debug)]
+enum Piece {
+    Text(String),
+    Action(String),
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Output(Expr),
+    If(Expr, Vec<Node>, Vec<Node>),
+    Range {
+        vars: Vec<String>,
+        expr: Expr,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+#[derive(Debug)]
+enum Root {
+    Dot,
+    Variable(String),
+}
+
+#[derive(Debug)]
+enum Expr {
+    Path(Root, Vec<String>),
+    /// A plain `{{name}}` variable of the expander
+    Var(String),
+    Str(String),
+    Num(i64),
+    Call(String, Vec<Expr>),
+}
+
+/// Split a template into text and `{{...}}` actions, applying `{{-`/`-}}` trimming
+fn split_actions(template: &str) -> Result<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    let mut trim_next = false;
+
+    while let Some(start) = rest.find("{{") {
+        let mut text = &rest[..start];
+        if trim_next {
+            text = text.trim_start();
+        }
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| template_error("unclosed action".to_string()))?;
+        let mut action = &after[..end];
+        if let Some(stripped) = action.strip_prefix('-') {
+            text = text.trim_end();
+            action = stripped;
+        }
+        trim_next = false;
+        if let Some(stripped) = action.strip_suffix('-') {
+            trim_next = true;
+            action = stripped;
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text.to_string()));
+        }
+        pieces.push(Piece::Action(action.trim().to_string()));
+        rest = &after[end + 2..];
+    }
+
+    let text = if trim_next { rest.trim_start() } else { rest };
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text.to_string()));
+    }
+    Ok(pieces)
+}
+
+/// Parse nodes until `{{else}}`, `{{end}}` or the end of input, returning
+/// the keyword that stopped parsing
+fn parse_nodes(pieces: &[Piece], pos: &mut usize) -> Result<(Vec<Node>, Option<String>)> {
+    let mut nodes = Vec::new();
+    while let Some(piece) = pieces.get(*pos) {
+        *pos += 1;
+        let action = match piece {
+            Piece::Text(text) => {
+                nodes.push(Node::Text(text.clone()));
+                continue;
+            }
+            Piece::Action(action) => action,
+        };
+
+        let (keyword, rest) = action.split_once(char::is_whitespace).unwrap_or((action, ""));
+        match keyword {
+            "else" | "end" => return Ok((nodes, Some(keyword.to_string()))),
+            "if" | "range" => {
+                let (vars, expr) = match rest.split_once(":=") {
+                    Some((vars, expr)) if keyword == "range" => (
+                        vars.split(',').map(|v| v.trim().trim_start_matches('$').to_string()).collect(),
+                        expr,
+                    ),
+                    _ => (Vec::new(), rest),
+                };
+                let expr = parse_expr(expr)?;
+                let (body, stop) = parse_nodes(pieces, pos)?;
+                let otherwise = match stop.as_deref() {
+                    Some("else") => match parse_nodes(pieces, pos)? {
+                        (otherwise, Some(end)) if end == "end" => otherwise,
+                        _ => return Err(template_error(format!("missing {{{{end}}}} for {}", keyword))),
+                    },
+                    Some("end") => Vec::new(),
+                    _ => return Err(template_error(format!("missing {{{{end}}}} for {}", keyword))),
+                };
+                nodes.push(if keyword == "if" {
+                    Node::If(expr, body, otherwise)
+                } else {
+                    Node::Range { vars, expr, body, otherwise }
+                });
+            }
+            _ => nodes.push(Node::Output(parse_expr(action)?)),
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn parse_expr(source: &str) -> Result<Expr> {
+    let mut words = Vec::new();
+    let mut chars = source.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut word = c.to_string();
+        if c == '"' {
+            for c in chars.by_ref() {
+                word.push(c);
+                if c == '"' {
+                    break;
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+        }
+        words.push(word);
+    }
+
+    match words.as_slice() {
+        [] => Err(template_error("empty action".to_string())),
+        [word] => parse_atom(word),
+        [name, args @ ..] => Ok(Expr::Call(name.clone(), args.iter().map(|a| parse_atom(a)).collect::<Result<_>>()?)),
+    }
+}
+
+fn parse_atom(word: &str) -> Result<Expr> {
+    let fields = |path: &str| path.split('.').filter(|f| !f.is_empty()).map(str::to_string).collect();
+    if let Some(path) = word.strip_prefix('.') {
+        return Ok(Expr::Path(Root::Dot, fields(path)));
+    }
+    if let Some(var) = word.strip_prefix('$') {
+        let (name, path) = var.split_once('.').unwrap_or((var, ""));
+        return Ok(Expr::Path(Root::Variable(name.to_string()), fields(path)));
+    }
+    if let Some(s) = word.strip_prefix('"').and_then(|w| w.strip_suffix('"')) {
+        return Ok(Expr::Str(s.to_string()));
+    }
+    if let Ok(n) = word.parse() {
+        return Ok(Expr::Num(n));
+    }
+    if word.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Ok(Expr::Var(word.to_string()));
+    }
+    Err(template_error(format!("unexpected \"{}\" in template", word)))
+}
+
+/// Text printed for a value: strings as-is, null as nothing, others as JSON
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Go template truthiness: false, 0, null and empty values are false
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+    }
 }
 
 impl Default for TemplateExpander {
@@ -113,6 +443,52 @@ mod tests {
         assert_eq!(result, "1 + 1 = 2");
     }
 
+    #[test]
+    fn test_expand_known_keeps_undefined_variables() {
+        let mut expander = TemplateExpander::new();
+        expander.add_var("short_commit", "abc1234");
+
+        let result = expander.expand_known("Fixed in {{short_commit}}, thanks {{ reply_to }}").unwrap();
+        assert_eq!(result, "Fixed in abc1234, thanks {{ reply_to }}");
+    }
+
+    #[test]
+    fn test_render_value_range_and_fields() {
+        let data = serde_json::json!({
+            "total": 2,
+            "items": [
+                {"path": "src/main.rs", "line": 10, "body": "Rename this", "author": {"login": "reviewer"}},
+                {"path": "src/lib.rs", "line": null, "body": "Add docs", "author": {"login": "bot"}},
+            ]
+        });
+        let mut expander = TemplateExpander::new();
+        expander.add_var("pr_number", "7");
+
+        let result = expander
+            .render_value("PR {{pr_number}} ({{len .items}}):\n{{range .items}}{{.path}}:{{.line}} {{.author.login}}\n{{end}}", &data)
+            .unwrap();
+        assert_eq!(result, "PR 7 (2):\nsrc/main.rs:10 reviewer\nsrc/lib.rs: bot\n");
+
+        let result = expander
+            .render_value("{{range $i, $t := .items -}}\n  {{$i}}={{if $t.line}}{{$t.line}}{{else}}-{{end}} {{truncate 5 .body}}\n{{- end}}", &data)
+            .unwrap();
+        assert_eq!(result, "0=10 Rena…1=- Add …");
+
+        let result = expander.render_value("{{range .missing}}x{{else}}none{{end}}", &data).unwrap();
+        assert_eq!(result, "none");
+    }
+
+    #[test]
+    fn test_render_value_errors() {
+        let expander = TemplateExpander::new();
+        let data = serde_json::json!({"items": [1]});
+        assert!(expander.render_value("{{range .items}}x", &data).is_err());
+        assert!(expander.render_value("{{end}}", &data).is_err());
+        assert!(expander.render_value("{{.items", &data).is_err());
+        assert!(expander.render_value("{{range .}}{{$nope}}{{end}}", &data).is_err());
+        assert!(expander.render_value("{{range .total}}{{end}}", &serde_json::json!({"total": 1})).is_err());
+    }
+
     #[test]
     fn test_expand_empty_template() {
         let mut expander = TemplateExpander::new();
@@ -177,5 +553,8 @@ mod tests {
 
         let result = expander.expand("PR #{{pr_number}} by {{author}}").unwrap();
         assert_eq!(result, "PR #123 by author");
+
+        let result = expander.expand("Applied in {{short_commit}}").unwrap();
+        assert_eq!(result, "Applied in abc123");
     }
 }
@@ -15,6 +15,41 @@ fn setup_test_env() {
     env::set_var("GH_COMMAND", mock_path.to_str().unwrap());
 }
 
+fn client() -> gh_reply::github::GhClient {
+    gh_reply::github::GhClient::new(None)
+}
+
+fn mock_log(thread_id: &str) -> PathBuf {
+    env::temp_dir().join(format!("gh-mock-{}.calls", thread_id))
+}
+
+/// GraphQL requests the mock logged under `name`, clearing the log
+fn mock_requests(name: &str) -> Vec<serde_json::Value> {
+    let log = mock_log(name);
+    let requests = fs::read_to_string(&log)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| {
+            // Requests without variables are logged as `query=<query>`
+            serde_json::from_str(line).unwrap_or_else(|_| serde_json::json!({ "query": line.trim_start_matches("query=") }))
+        })
+        .collect();
+    fs::remove_file(log).ok();
+    requests
+}
+
+/// The logged requests that ran a mutation
+fn mutations(requests: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    requests.into_iter().filter(|r| mutation_name(&r["query"]).is_some()).collect()
+}
+
+/// Name of the mutation a logged request ran, if it was one
+fn mutation_name(query: &serde_json::Value) -> Option<&str> {
+    let query = query.as_str()?.strip_prefix("mutation")?;
+    let start = query.find('{')? + 1;
+    query[start..].split(['(', ' ', '{']).find(|s| !s.is_empty())
+}
+
 fn cleanup_draft_store() {
     let draft_path: PathBuf = ".git/info/gh-reply-drafts.json".into();
     if draft_path.exists() {
@@ -31,7 +66,7 @@ mod list_command_tests {
         setup_test_env();
         // list command internally calls GhClient::list_prs
         // which uses gh-mock via GH_COMMAND
-        let result = gh_reply::commands::list::list("open").await;
+        let result = gh_reply::commands::list::list(&client(), "open").await;
         assert!(result.is_ok());
     }
 
@@ -39,7 +74,7 @@ mod list_command_tests {
     async fn test_list_command_invalid_state_fallback() {
         setup_test_env();
         // Invalid state should fallback to "open"
-        let result = gh_reply::commands::list::list("invalid_state").await;
+        let result = gh_reply::commands::list::list(&client(), "invalid_state").await;
         assert!(result.is_ok());
     }
 }
@@ -52,7 +87,7 @@ mod comment_command_tests {
     async fn test_comment_list() {
         setup_test_env();
         // This will use the mock gh command
-        let result = gh_reply::commands::comment::list(123, false, None, None, None, 1, 10).await;
+        let result = gh_reply::commands::comment::list(&client(), 123, false, None, None, None, 1, 10, None).await;
         // May fail if mock doesn't return proper GraphQL response
         // but should not panic
         let _ = result;
@@ -61,22 +96,335 @@ mod comment_command_tests {
     #[tokio::test]
     async fn test_comment_show() {
         setup_test_env();
-        let result = gh_reply::commands::comment::show(123, "THREAD_123", None).await;
+        let result = gh_reply::commands::comment::show(&client(), 123, Some("THREAD_123"), None, None, None, false).await;
         let _ = result;
     }
 
+    #[tokio::test]
+    async fn test_select_threads_by_index() {
+        setup_test_env();
+        let targets = vec!["2".to_string(), "PRRT_custom".to_string()];
+        let ids = gh_reply::commands::comment::select_threads(&client(), 123, &targets, None, true)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["PRRT_thread2", "PRRT_custom"]);
+    }
+
+    #[tokio::test]
+    async fn test_select_threads_by_filter() {
+        setup_test_env();
+        // Only unresolved threads whose last reply is ours
+        let ids = gh_reply::commands::comment::select_threads(&client(), 123, &[], Some("last-author:@me"), true)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["PRRT_thread1"]);
+    }
+
+    #[tokio::test]
+    async fn test_select_threads_requires_target() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::select_threads(&client(), 123, &[], None, true).await;
+        assert!(matches!(result, Err(gh_reply::error::GhReplyError::InvalidInput(_))));
+
+        let err = gh_reply::commands::comment::select_threads(&client(), 123, &[], Some("bogus"), true)
+            .await
+            .unwrap_err();
+        assert_eq!((err.kind(), err.exit_code(), err.is_transient()), ("invalid_input", 2, false));
+    }
+
+    #[test]
+    fn test_usage_errors_follow_error_format() {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_gh-reply"))
+            .args(["--error-format", "json", "comment", "reply", "12"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2));
+        let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+        assert_eq!(error["kind"], "invalid_input");
+        assert!(error["message"].as_str().unwrap().contains("<THREAD_ID>"));
+    }
+
+    #[test]
+    fn test_bad_output_options_are_invalid_input() {
+        let mock = env::current_dir().unwrap().join("tests/fixtures/gh-mock");
+        for args in [["--jq", ".[0].number.x"], ["--json", "nope"]] {
+            let output = std::process::Command::new(env!("CARGO_BIN_EXE_gh-reply"))
+                .env("GH_COMMAND", &mock)
+                .args(["--error-format", "json", "list"])
+                .args(args)
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(2), "{:?}", args);
+            let error: serde_json::Value = serde_json::from_slice(&output.stderr).unwrap();
+            assert_eq!(error["kind"], "invalid_input", "{:?}", args);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_select_threads_index_out_of_range() {
+        setup_test_env();
+        let targets = vec!["99".to_string()];
+        let err = gh_reply::commands::comment::select_threads(&client(), 123, &targets, None, true)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, gh_reply::error::GhReplyError::IndexOutOfRange { index: 99, .. }));
+        assert_eq!((err.kind(), err.exit_code()), ("index_out_of_range", 6));
+    }
+
+    #[tokio::test]
+    async fn test_select_threads_unknown_id_with_filter() {
+        setup_test_env();
+        let targets = vec!["PRRT_thread1".to_string(), "PRRT_nope".to_string()];
+        let err = gh_reply::commands::comment::select_threads(&client(), 123, &targets, Some("path:src/"), true)
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Thread PRRT_nope not found on test-owner/test-repo#123");
+        assert_eq!(err.exit_code(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_select_outdated_threads() {
+        setup_test_env();
+        let ids = gh_reply::commands::comment::select_threads(&client(), 123, &[], Some("outdated:true"), true)
+            .await
+            .unwrap();
+        assert_eq!(ids, vec!["PRRT_thread4"]);
+    }
+
+    #[tokio::test]
+    async fn test_comment_resolve_outdated_with_reply() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::resolve(
+            &client(),
+            123,
+            &[],
+            None,
+            true,
+            Some("Addressed by later changes"),
+            false,
+        )
+        .await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_comment_resolve_skipped_by_policy_gets_no_reply() {
+        use gh_reply::policy::ResolvePolicy;
+        setup_test_env();
+
+        for (n, policy) in [ResolvePolicy::Never, ResolvePolicy::OnlyBotThreads].into_iter().enumerate() {
+            let thread_id = format!("PRRT_logged_{}{}", std::process::id(), n);
+            let log = env::temp_dir().join(format!("gh-mock-{}.calls", thread_id));
+            let result = gh_reply::commands::comment::resolve_threads(
+                &client(),
+                123,
+                policy,
+                &[thread_id],
+                Some("Addressed"),
+            )
+            .await;
+            assert!(result.is_ok());
+
+            let requests = fs::read_to_string(&log).unwrap_or_default();
+            assert!(!requests.contains("addPullRequestReviewThreadReply"), "{:?}", policy);
+            assert!(!requests.contains("resolveReviewThread"), "{:?}", policy);
+            fs::remove_file(log).ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_suggestion_not_posted_when_policy_keeps_thread_open() {
+        use gh_reply::policy::ResolvePolicy;
+        setup_test_env();
+
+        let thread_id = format!("PRRT_logged_{}2", std::process::id());
+        let result = gh_reply::commands::comment::post_suggestion(
+            &client(),
+            123,
+            &thread_id,
+            "```suggestion\nfixed\n```",
+            true,
+            ResolvePolicy::Never,
+        )
+        .await;
+        assert_eq!(result.unwrap_err().kind(), "invalid_input");
+
+        let log = mock_log(&thread_id);
+        let requests = fs::read_to_string(&log).unwrap_or_default();
+        assert!(!requests.contains("addPullRequestReviewThreadReply"));
+        fs::remove_file(log).ok();
+    }
+
+    #[tokio::test]
+    async fn test_comment_resolve_dry_run() {
+        setup_test_env();
+        let targets = vec!["1".to_string()];
+        let result = gh_reply::commands::comment::resolve(&client(), 123, &targets, None, false, None, true).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_comment_edit_last_reply_dry_run() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::edit(&client(), None, Some(123), Some("1"), Some("Fixed typo"), false, true).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_comment_edit_requires_body() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::edit(&client(), Some("PRRC_reply1"), None, None, None, false, true).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_comment_delete_dry_run() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::delete(&client(), Some("PRRC_reply1"), None, None, true).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_comment_reply_posts() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::reply(&client(), 123, Some("PRRT_thread1"), None, "Thanks", false, false).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_comment_new_thread() {
+        setup_test_env();
+        mock_requests("PR_logged_9010");
+        let result = gh_reply::commands::comment::new_thread(&client(), 9010, "src/x.rs", 42, Some(40), "left", "This changed because...", false, false).await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        // A pending review is started, the thread added to it, then submitted
+        let requests = mutations(mock_requests("PR_logged_9010"));
+        let mutations: Vec<&str> = requests.iter().filter_map(|r| mutation_name(&r["query"])).collect();
+        assert_eq!(mutations, ["addPullRequestReview", "addPullRequestReviewThread", "submitPullRequestReview"]);
+        let thread = &requests[1]["variables"];
+        assert_eq!(thread["reviewId"], "PRR_logged_9010");
+        assert_eq!(thread["path"], "src/x.rs");
+        assert_eq!(thread["line"], 42);
+        assert_eq!(thread["startLine"], 40);
+        assert_eq!(thread["side"], "LEFT");
+        assert_eq!(requests[2]["variables"]["reviewId"], "PRR_logged_9010");
+    }
+
+    #[tokio::test]
+    async fn test_comment_new_thread_deletes_review_when_submit_fails() {
+        setup_test_env();
+        mock_requests("PRR_unsubmittable");
+
+        let result = gh_reply::commands::comment::new_thread(&client(), 666, "src/x.rs", 42, None, "RIGHT", "Note", false, false).await;
+        assert!(result.is_err());
+
+        // The review that could not be submitted is not left pending
+        let requests = mock_requests("PRR_unsubmittable");
+        let mutations: Vec<&str> = requests.iter().filter_map(|r| mutation_name(&r["query"])).collect();
+        assert_eq!(mutations, ["addPullRequestReviewThread", "submitPullRequestReview", "deletePullRequestReview"]);
+        assert!(requests.iter().all(|r| r["variables"]["reviewId"] == "PRR_unsubmittable"));
+    }
+
+    #[tokio::test]
+    async fn test_comment_new_thread_drafts_keep_both_sides() {
+        setup_test_env();
+        gh_reply::commands::comment::new_thread(&client(), 782, "src/x.rs", 42, None, "LEFT", "Why was this removed?", true, false).await.unwrap();
+        gh_reply::commands::comment::new_thread(&client(), 782, "src/x.rs", 42, None, "RIGHT", "Nice", true, false).await.unwrap();
+
+        let store = gh_reply::store::DraftStore::load().await.unwrap();
+        let drafts = store.get_all_drafts(782);
+        assert_eq!(drafts["new:src/x.rs:42:LEFT"].body, "Why was this removed?");
+        assert_eq!(drafts["new:src/x.rs:42:RIGHT"].body, "Nice");
+        gh_reply::commands::draft::clear(782).await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_comment_new_thread_invalid_range() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::new_thread(&client(), 123, "src/x.rs", 40, Some(42), "RIGHT", "Note", false, true).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_comment_suggest_requires_line() {
+        setup_test_env();
+        // Thread 3 is a file-level thread without a line
+        let result = gh_reply::commands::comment::suggest(&client(), 123, None, Some(3), None, false, false, true).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_comment_apply_dry_run() {
+        setup_test_env();
+        mock_requests("PR_logged_9041");
+        let targets = vec!["2".to_string()];
+        let report = gh_reply::commands::comment::apply_suggestions(&client(), 9041, &targets, None, Some("Applied"), false, true)
+            .await
+            .unwrap();
+
+        // The review commit isn't in this repository, so the thread is reported as a conflict
+        assert_eq!(report["applied"], serde_json::json!([]));
+        assert_eq!(report["conflicts"][0]["threadId"], "PRRT_thread2");
+        assert_eq!(report["conflicts"][0]["path"], "src/lib.rs");
+
+        // Nothing was sent or drafted
+        let requests = mock_requests("PR_logged_9041");
+        assert!(!requests.is_empty());
+        assert!(requests.iter().all(|r| mutation_name(&r["query"]).is_none()));
+        let store = gh_reply::store::DraftStore::load().await.unwrap();
+        assert!(store.get_all_drafts(9041).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_comment_show_with_context() {
+        setup_test_env();
+        let result = gh_reply::commands::comment::show(&client(), 123, None, Some(1), None, Some(2), false).await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_comment_context_highlights_original_line() {
+        setup_test_env();
+        let client = client();
+        let mut thread = client.get_review_threads(123).await.unwrap().remove(0);
+        // Later pushes moved the commented lines; the diff hunk keeps the original numbering
+        thread.start_line = Some(13);
+        thread.line = Some(14);
+
+        let rendered = gh_reply::commands::comment::render_with_context(&client, &thread, 1, false).await.unwrap();
+        let highlighted: Vec<&str> = rendered.lines().filter(|l| l.starts_with('>')).collect();
+        assert_eq!(highlighted, [">  9 | line 9", "> 10 |+line ten"], "{}", rendered);
+    }
+
     #[tokio::test]
     async fn test_comment_reply_with_resolve() {
         setup_test_env();
-        let result = gh_reply::commands::comment::reply(123, "THREAD_123", "Test reply", true, true).await;
+        let result = gh_reply::commands::comment::reply(&client(), 123, Some("THREAD_123"), None, "Test reply", true, true).await;
         // Dry run should succeed
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_resolve_policy_lookup_errors_are_not_ignored() {
+        use gh_reply::policy::ResolvePolicy;
+        setup_test_env();
+
+        let thread_id = format!("PRRT_missing_{}1", std::process::id());
+        let err = gh_reply::commands::comment::resolve_with_policy(&client(), ResolvePolicy::OnlyOwnThreads, &thread_id, "testuser")
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), "not_found");
+        fs::remove_file(env::temp_dir().join(format!("gh-mock-{}.calls", thread_id))).ok();
+    }
 }
 
 #[cfg(test)]
 mod draft_command_tests {
     use super::*;
+    use gh_reply::github::cache::Cache;
+    use gh_reply::github::policy::RequestPolicy;
+    use std::time::{Duration, Instant};
 
     #[tokio::test]
     async fn test_draft_add_and_show() {
@@ -84,11 +432,11 @@ mod draft_command_tests {
         cleanup_draft_store();
 
         // Add a draft with resolve flag
-        let result = gh_reply::commands::draft::add(123, "thread-1", "Test message", true).await;
+        let result = gh_reply::commands::draft::add(&client(), 123, Some("thread-1"), None, "Test message", true).await;
         assert!(result.is_ok());
 
         // Show drafts
-        let result = gh_reply::commands::draft::show(123).await;
+        let result = gh_reply::commands::draft::show(&client(), 123).await;
         assert!(result.is_ok());
 
         cleanup_draft_store();
@@ -100,7 +448,7 @@ mod draft_command_tests {
         cleanup_draft_store();
 
         // Add a draft
-        gh_reply::commands::draft::add(123, "thread-1", "Test message", false).await.ok();
+        gh_reply::commands::draft::add(&client(), 123, Some("thread-1"), None, "Test message", false).await.ok();
 
         // Clear drafts
         let result = gh_reply::commands::draft::clear(123).await;
@@ -115,12 +463,275 @@ mod draft_command_tests {
         cleanup_draft_store();
 
         // Add a draft
-        gh_reply::commands::draft::add(123, "thread-1", "Test message", true).await.ok();
+        gh_reply::commands::draft::add(&client(), 123, Some("thread-1"), None, "Test message", true).await.ok();
 
         // Send drafts in dry run mode
-        let result = gh_reply::commands::draft::send(123, false, true).await;
+        let result = gh_reply::commands::draft::send(&client(), 123, false, true, false, None, "COMMENT").await;
         assert!(result.is_ok());
 
         cleanup_draft_store();
     }
+
+    #[tokio::test]
+    async fn test_draft_suggest_fixed_dry_run() {
+        setup_test_env();
+        mock_requests("PR_logged_9050");
+        // The mock threads refer to a commit that doesn't exist locally,
+        // so every unresolved thread with lines is reported as skipped
+        let report = gh_reply::commands::draft::fixed_thread_drafts(&client(), 9050, "Fixed in {{short_commit}}", true)
+            .await
+            .unwrap();
+        assert_eq!(report["drafted"], serde_json::json!([]));
+        let skipped: Vec<&str> = report["skipped"].as_array().unwrap().iter().filter_map(|s| s["threadId"].as_str()).collect();
+        assert_eq!(skipped, ["PRRT_thread1", "PRRT_thread2", "PRRT_thread4"]);
+
+        let requests = mock_requests("PR_logged_9050");
+        assert!(!requests.is_empty());
+        assert!(requests.iter().all(|r| mutation_name(&r["query"]).is_none()));
+        let store = gh_reply::store::DraftStore::load().await.unwrap();
+        assert!(store.get_all_drafts(9050).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_draft_send_as_review() {
+        setup_test_env();
+        mock_requests("PR_logged_9030");
+
+        // Add drafts for a thread, for the PR itself and for a new thread
+        gh_reply::commands::draft::add(&client(), 9030, Some("PRRT_thread1"), None, "Renamed", false).await.unwrap();
+        gh_reply::commands::draft::add(&client(), 9030, Some("main"), None, "Thanks for the review", false).await.unwrap();
+        gh_reply::commands::comment::new_thread(&client(), 9030, "src/x.rs", 42, None, "RIGHT", "Note", true, false).await.unwrap();
+
+        // Submit them as a single review
+        let result = gh_reply::commands::draft::send(&client(), 9030, false, false, true, Some("Summary"), "approve").await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        // The review is started first and submitted last, with every comment attached to it
+        let requests = mutations(mock_requests("PR_logged_9030"));
+        let mutations: Vec<&str> = requests.iter().filter_map(|r| mutation_name(&r["query"])).collect();
+        assert_eq!(mutations.len(), 4, "{:?}", mutations);
+        assert_eq!(mutations[0], "addPullRequestReview");
+        assert_eq!(mutations[3], "submitPullRequestReview");
+        let find = |name: &str| &requests[mutations.iter().position(|m| *m == name).unwrap()]["variables"];
+        let reply = find("addPullRequestReviewThreadReply");
+        assert_eq!(reply["reviewId"], "PRR_logged_9030");
+        assert_eq!(reply["threadId"], "PRRT_thread1");
+        let thread = find("addPullRequestReviewThread");
+        assert_eq!(thread["reviewId"], "PRR_logged_9030");
+        assert_eq!(thread["path"], "src/x.rs");
+        let submit = find("submitPullRequestReview");
+        assert_eq!(submit["reviewId"], "PRR_logged_9030");
+        assert_eq!(submit["event"], "APPROVE");
+        assert_eq!(submit["body"], "Summary\n\nThanks for the review");
+
+        let store = gh_reply::store::DraftStore::load().await.unwrap();
+        assert!(store.get_all_drafts(9030).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_draft_send_as_review_clears_drafts_when_resolve_fails() {
+        setup_test_env();
+        mock_requests("PR_logged_9031");
+        let thread_id = format!("PRRT_unresolvable_{}", std::process::id());
+
+        gh_reply::commands::draft::add(&client(), 9031, Some(&thread_id), None, "Done", true).await.unwrap();
+        gh_reply::commands::draft::add(&client(), 9031, Some("PRRT_thread1"), None, "Renamed", false).await.unwrap();
+
+        // The review is submitted; the failed resolve doesn't keep its drafts around
+        let result = gh_reply::commands::draft::send(&client(), 9031, false, false, true, None, "COMMENT").await;
+        assert!(result.is_ok(), "{:?}", result);
+
+        let requests = mock_requests("PR_logged_9031");
+        assert!(requests.iter().any(|r| mutation_name(&r["query"]) == Some("submitPullRequestReview")));
+        let store = gh_reply::store::DraftStore::load().await.unwrap();
+        assert!(store.get_all_drafts(9031).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_draft_dry_run_does_not_save() {
+        setup_test_env();
+
+        let result = gh_reply::commands::comment::new_thread(&client(), 778, "src/x.rs", 42, None, "RIGHT", "Note", true, true).await;
+        assert!(result.is_ok());
+
+        let store = gh_reply::store::DraftStore::load().await.unwrap();
+        assert!(store.get_all_drafts(778).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_draft_send_keeps_rendered_body_verbatim() {
+        setup_test_env();
+        let thread_id = format!("PRRT_logged_{}", std::process::id());
+        let body = "```suggestion\nprintln!(\"{{value}}\");\n```";
+
+        let mut store = gh_reply::store::DraftStore::load().await.unwrap();
+        store.add_draft(779, &thread_id, gh_reply::types::DraftEntry {
+            body: body.to_string(),
+            path: None,
+            line: None,
+            start_line: None,
+            side: None,
+            original_comment: None,
+            resolve: None,
+            rendered: Some(true),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+        });
+        store.save().unwrap();
+
+        let result = gh_reply::commands::draft::send(&client(), 779, false, false, false, None, "COMMENT").await;
+        assert!(result.is_ok());
+
+        let log = fs::read_to_string(mock_log(&thread_id)).unwrap();
+        assert!(log.lines().any(|request| request.contains("addPullRequestReviewThreadReply") && request.contains("{{value}}")));
+        fs::remove_file(mock_log(&thread_id)).ok();
+    }
+
+    #[tokio::test]
+    async fn test_draft_send_reports_sent_comments_on_failure() {
+        setup_test_env();
+        let thread_id = format!("PRRT_unresolvable_{}", std::process::id());
+        gh_reply::commands::draft::add(&client(), 781, Some(&thread_id), None, "Done", true).await.unwrap();
+
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_gh-reply"))
+            .args(["comment", "draft", "send", "781"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(11));
+        let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(report["prNumber"], 781);
+        assert_eq!(report["sent"][0]["id"], "PRRC_new1");
+
+        // The posted reply is not sent again
+        let store = gh_reply::store::DraftStore::load().await.unwrap();
+        assert!(store.get_draft(781, &thread_id).is_none());
+        gh_reply::commands::draft::clear(781).await.ok();
+    }
+
+    /// Number of GraphQL calls the mock logged for a failing thread
+    fn mock_calls(thread_id: &str) -> usize {
+        let log = mock_log(thread_id);
+        let calls = fs::read_to_string(&log).map(|c| c.lines().count()).unwrap_or(0);
+        fs::remove_file(log).ok();
+        calls
+    }
+
+    #[tokio::test]
+    async fn test_graphql_not_found_is_not_retried() {
+        setup_test_env();
+        let thread_id = format!("PRRT_missing_{}", std::process::id());
+        let err = client().post_reply(12, &thread_id, "Thanks").await.unwrap_err();
+
+        assert_eq!(err.to_string(), format!("Thread {} not found on test-owner/test-repo#12", thread_id));
+        assert_eq!(err.exit_code(), 5);
+        assert_eq!(mock_calls(&thread_id), 1);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_server_error_is_retried() {
+        setup_test_env();
+        let thread_id = format!("PRRT_flaky_{}", std::process::id());
+        let client = client().policy(RequestPolicy { retry_delay: Duration::from_millis(10), ..RequestPolicy::default() });
+        let err = client.get_thread_comments(&thread_id).await.unwrap_err();
+
+        assert_eq!(err.kind(), "server_error");
+        assert!(err.to_string().contains("try again later"));
+        // The first attempt and the three default retries
+        assert_eq!(mock_calls(&thread_id), 4);
+    }
+
+    #[tokio::test]
+    async fn test_mutation_is_not_retried_blindly() {
+        setup_test_env();
+        let thread_id = format!("PRRT_flaky_{}1", std::process::id());
+        let err = client().resolve_thread(&thread_id).await.unwrap_err();
+
+        assert_eq!(err.kind(), "server_error");
+        assert_eq!(mock_calls(&thread_id), 1);
+    }
+
+    fn impatient_client(retries: u32) -> gh_reply::github::GhClient {
+        client().policy(RequestPolicy {
+            timeout: Some(Duration::from_millis(200)),
+            retries,
+            retry_delay: Duration::ZERO,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_hung_query_is_killed_and_retried() {
+        setup_test_env();
+        let thread_id = format!("PRRT_slow_{}", std::process::id());
+        let started = Instant::now();
+        let err = impatient_client(1).get_thread_comments(&thread_id).await.unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!((err.kind(), err.exit_code()), ("timeout", 14));
+        assert_eq!(err.to_string(), "gh api graphql did not respond within 200ms and was stopped");
+        assert_eq!(mock_calls(&thread_id), 2);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_overlap() {
+        setup_test_env();
+        let (first, second) = (format!("PRRT_slow_{}2", std::process::id()), format!("PRRT_slow_{}3", std::process::id()));
+        let client = client().policy(RequestPolicy {
+            timeout: Some(Duration::from_millis(500)),
+            retries: 0,
+            retry_delay: Duration::ZERO,
+        });
+
+        // Both requests wait on gh at the same time, even on a single-threaded runtime
+        let started = Instant::now();
+        let (a, b) = tokio::join!(client.get_thread_comments(&first), client.get_thread_comments(&second));
+        assert!(started.elapsed() < Duration::from_millis(900));
+        assert_eq!((a.unwrap_err().kind(), b.unwrap_err().kind()), ("timeout", "timeout"));
+        assert_eq!((mock_calls(&first), mock_calls(&second)), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_hung_mutation_is_not_retried() {
+        setup_test_env();
+        let thread_id = format!("PRRT_slow_{}1", std::process::id());
+        let err = impatient_client(3).resolve_thread(&thread_id).await.unwrap_err();
+
+        assert_eq!(err.kind(), "timeout");
+        assert_eq!(mock_calls(&thread_id), 1);
+    }
+
+    #[tokio::test]
+    async fn test_secondary_rate_limit_waits_for_retry_after() {
+        setup_test_env();
+        let thread_id = format!("PRRT_throttled_{}", std::process::id());
+        let client = client();
+        let started = Instant::now();
+        client.resolve_thread(&thread_id).await.unwrap();
+
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(mock_calls(&thread_id), 2);
+        assert_eq!(client.rate_limit().map(|rate| rate.remaining), Some(4999));
+    }
+
+    #[tokio::test]
+    async fn test_cached_threads_are_reused_until_a_mutation() {
+        setup_test_env();
+        let dir = env::temp_dir().join(format!("gh-reply-cache-{}", std::process::id()));
+        let client = client().cache(Cache::new(dir.clone(), Duration::from_secs(60)));
+
+        let threads = client.get_review_threads(888).await.unwrap();
+        assert_eq!(threads.len(), 4);
+
+        // A second lookup is answered from the cache file
+        let entry = dir.join("prs/test-owner/test-repo/888/threads.json");
+        let mut cached: serde_json::Value = serde_json::from_str(&fs::read_to_string(&entry).unwrap()).unwrap();
+        cached["value"].as_array_mut().unwrap().truncate(1);
+        fs::write(&entry, cached.to_string()).unwrap();
+        assert_eq!(client.get_review_threads(888).await.unwrap().len(), 1);
+
+        // Mutations drop the PR's entries
+        client.resolve_thread("PRRT_thread1").await.unwrap();
+        assert!(!entry.exists());
+        assert_eq!(client.get_review_threads(888).await.unwrap().len(), 4);
+
+        fs::remove_dir_all(dir).ok();
+    }
 }
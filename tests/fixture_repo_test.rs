@@ -0,0 +1,235 @@
+// Integration tests for commands that read local git history. They run in
+// a throwaway repository, so this file is its own test binary: the working
+// directory is process-wide.
+use gh_reply::store::DraftStore;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Commits of the fixture repository
+struct Fixture {
+    dir: PathBuf,
+    /// Changes line 10 of src/main.rs (thread 1)
+    rename: String,
+    /// Changes line 7 of src/old.rs (outdated thread 4)
+    handle: String,
+}
+
+/// Serializes tests that load and save the fixture's draft store
+static STORE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=Test", "-c", "user.email=test@example.com", "-c", "commit.gpgsign=false"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+fn commit(dir: &Path, files: &[(&str, String)], message: &str) -> String {
+    for (path, content) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", message]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+fn lines(prefix: &str, count: u32, replace: Option<(u32, &str)>) -> String {
+    (1..=count)
+        .map(|n| match replace {
+            Some((line, text)) if line == n => format!("{}\n", text),
+            _ => format!("{} {}\n", prefix, n),
+        })
+        .collect()
+}
+
+/// Create the fixture repository, make it the working directory and point
+/// the gh mock at its base commit
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let mock = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/gh-mock");
+        let dir = env::temp_dir().join(format!("gh-reply-fixture-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        git(&dir, &["init", "-q"]);
+
+        let base = commit(
+            &dir,
+            &[
+                ("src/main.rs", lines("line", 12, None)),
+                ("src/old.rs", lines("old", 8, None)),
+                ("src/lib.rs", lines("lib", 6, None)),
+            ],
+            "Initial commit",
+        );
+        let rename = commit(
+            &dir,
+            &[("src/main.rs", lines("line", 12, Some((10, "line ten"))))],
+            "Rename line ten\n\nAddresses-thread: PRRT_thread1\n",
+        );
+        let handle = commit(
+            &dir,
+            &[("src/old.rs", lines("old", 8, Some((7, "handled 7"))))],
+            "Handle the error\n\nAddresses-thread: 4\nReply: Handled in {{short_commit}}\n",
+        );
+
+        env::set_current_dir(&dir).unwrap();
+        env::set_var("GH_COMMAND", mock);
+        env::set_var("GH_MOCK_COMMIT_OID", &base);
+        Fixture { dir, rename, handle }
+    })
+}
+
+fn client() -> gh_reply::github::GhClient {
+    gh_reply::github::GhClient::new(None)
+}
+
+/// GraphQL requests the mock logged for PR `pr_number` (9xxx), clearing the log
+fn mock_requests(pr_number: u32) -> Vec<serde_json::Value> {
+    let log = env::temp_dir().join(format!("gh-mock-PR_logged_{}.calls", pr_number));
+    let requests = std::fs::read_to_string(&log)
+        .unwrap_or_default()
+        .lines()
+        .map(|line| {
+            // Requests without variables are logged as `query=<query>`
+            serde_json::from_str(line).unwrap_or_else(|_| serde_json::json!({ "query": line.trim_start_matches("query=") }))
+        })
+        .collect();
+    std::fs::remove_file(log).ok();
+    requests
+}
+
+fn is_mutation(request: &serde_json::Value) -> bool {
+    request["query"].as_str().is_some_and(|q| q.starts_with("mutation"))
+}
+
+#[tokio::test]
+async fn test_draft_suggest_fixed_finds_fixing_commits() {
+    let fixture = fixture();
+    let _lock = STORE_LOCK.lock().await;
+
+    let result = gh_reply::commands::draft::suggest_fixed(&client(), 501, "Fixed in {{short_commit}}", false).await;
+    assert!(result.is_ok(), "{:?}", result);
+
+    let store = DraftStore::load().await.unwrap();
+    let drafts = store.get_all_drafts(501);
+    let mut ids: Vec<&String> = drafts.keys().collect();
+    ids.sort();
+    assert_eq!(ids, vec!["PRRT_thread1", "PRRT_thread4"]);
+
+    // The outdated thread is followed from its original commit and line
+    assert_eq!(drafts["PRRT_thread1"].body, format!("Fixed in {}", &fixture.rename[..7]));
+    assert_eq!(drafts["PRRT_thread4"].body, format!("Fixed in {}", &fixture.handle[..7]));
+    assert_eq!(drafts["PRRT_thread4"].resolve, Some(true));
+}
+
+#[tokio::test]
+async fn test_draft_suggest_fixed_dry_run() {
+    let fixture = fixture();
+    let _lock = STORE_LOCK.lock().await;
+    mock_requests(9051);
+
+    let report = gh_reply::commands::draft::fixed_thread_drafts(&client(), 9051, "Fixed in {{short_commit}}", true)
+        .await
+        .unwrap();
+    let drafted: Vec<(&str, &str)> = report["drafted"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| (d["threadId"].as_str().unwrap(), d["commit"].as_str().unwrap()))
+        .collect();
+    assert_eq!(drafted, [("PRRT_thread1", fixture.rename.as_str()), ("PRRT_thread4", fixture.handle.as_str())]);
+    assert_eq!(report["drafted"][0]["body"], format!("Fixed in {}", &fixture.rename[..7]));
+
+    // Nothing is saved, sent or changed in the worktree
+    assert!(DraftStore::load().await.unwrap().get_all_drafts(9051).is_empty());
+    let requests = mock_requests(9051);
+    assert!(!requests.is_empty());
+    assert!(!requests.iter().any(is_mutation));
+    assert_eq!(git(&fixture.dir, &["status", "--porcelain"]), "");
+}
+
+#[tokio::test]
+async fn test_suggestion_draft_keeps_line_range() {
+    fixture();
+    let _lock = STORE_LOCK.lock().await;
+
+    let result = gh_reply::commands::comment::suggest(&client(), 504, Some("PRRT_thread1"), None, None, false, true, false).await;
+    assert!(result.is_ok(), "{:?}", result);
+
+    let store = DraftStore::load().await.unwrap();
+    let draft = store.get_draft(504, "PRRT_thread1").unwrap();
+    assert_eq!((draft.start_line, draft.line), (Some(9), Some(10)));
+    assert_eq!(draft.side.as_deref(), Some("RIGHT"));
+    assert_eq!(draft.body, "```suggestion\nline 9\nline ten\n```");
+}
+
+#[tokio::test]
+async fn test_draft_from_commits_reads_trailers() {
+    let fixture = fixture();
+    let _lock = STORE_LOCK.lock().await;
+
+    let result = gh_reply::commands::draft::from_commits(
+        &client(),
+        502,
+        Some("HEAD~2..HEAD"),
+        "Addressed in {{short_commit}}",
+        true,
+        false,
+    )
+    .await;
+    assert!(result.is_ok(), "{:?}", result);
+
+    let store = DraftStore::load().await.unwrap();
+    let drafts = store.get_all_drafts(502);
+    assert_eq!(drafts.len(), 2);
+    assert_eq!(drafts["PRRT_thread1"].body, format!("Addressed in {}", &fixture.rename[..7]));
+    assert_eq!(drafts["PRRT_thread4"].body, format!("Handled in {}", &fixture.handle[..7]));
+    assert!(drafts.values().all(|draft| draft.resolve == Some(true)));
+
+    // A dry run reports the same drafts without saving them
+    let result = gh_reply::commands::draft::from_commits(&client(), 503, Some("HEAD~2..HEAD"), "x", false, true).await;
+    assert!(result.is_ok());
+    assert!(DraftStore::load().await.unwrap().get_all_drafts(503).is_empty());
+}
+
+#[tokio::test]
+async fn test_comment_apply_uses_latest_suggestion() {
+    let fixture = fixture();
+    let _lock = STORE_LOCK.lock().await;
+    mock_requests(9040);
+    let original = lines("lib", 6, None);
+    let targets = vec!["2".to_string()];
+
+    // A dry run reports what would change without touching the worktree
+    let report = gh_reply::commands::comment::apply_suggestions(&client(), 9040, &targets, None, None, false, true)
+        .await
+        .unwrap();
+    let expected = serde_json::json!({
+        "applied": [{"threadId": "PRRT_thread2", "path": "src/lib.rs", "startLine": 5, "endLine": 5}],
+        "conflicts": [],
+    });
+    assert_eq!(report, expected);
+    assert_eq!(std::fs::read_to_string(fixture.dir.join("src/lib.rs")).unwrap(), original);
+    let requests = mock_requests(9040);
+    assert!(!requests.is_empty());
+    assert!(!requests.iter().any(is_mutation));
+
+    // The suggestion revised in a later reply is the one applied
+    let report = gh_reply::commands::comment::apply_suggestions(&client(), 9040, &targets, None, None, false, false)
+        .await
+        .unwrap();
+    assert_eq!(report, expected);
+    let applied = std::fs::read_to_string(fixture.dir.join("src/lib.rs")).unwrap();
+    std::fs::write(fixture.dir.join("src/lib.rs"), &original).unwrap();
+    assert_eq!(applied, lines("lib", 6, Some((5, "pub const FIVE: u32 = 5;"))));
+}